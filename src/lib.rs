@@ -42,10 +42,310 @@ use syn::parse_macro_input;
 ///
 /// For all bar `Default`, all data in the `struct` or `enum` must satisfy the bound. For `Default` on `enum`s,
 /// only the data stored in the default enum must satisfy the `Default` trait bound.
+///
+/// Only `Copy` and `Clone` can be derived for a `union`. As with std, `Clone` is implemented by copying, and so
+/// requires that the `union` is `Copy`.
+///
+/// Generic parameters may be given `#[cfg(...)]` attributes. As the type can't be named with a parameter which may
+/// not be there, a separate impl of each trait is generated for every combination of such parameters being present
+/// or absent, and so `n` of them give `2^n` impls of each trait. Each impl leaves out the fields naming an absent
+/// parameter, and the variants and fields whose `#[cfg]`s include all of those of an absent parameter.
+///
+/// When `Ord` and `PartialOrd` are derived together, `PartialOrd` is implemented as `Some(self.cmp(other))`,
+/// and so takes the same bounds as `Ord`. Similarly, when `Copy` and `Clone` are derived together on a type
+/// without type parameters, `Clone` is implemented as `*self`, unless a field is cloned by a function given with
+/// `with(Clone = ...)`.
+///
+/// For enums without fields, which may have many variants, the generated code avoids matching on every variant
+/// where it can. `PartialEq` and `Hash` always use [`std::mem::discriminant`]. The other compact forms need the
+/// value to be copied out of `&self`, and so only apply when `Copy` is derived by the same `#[perfect_derive(...)]`:
+/// `Clone` is then `*self`, `Ord` and `PartialOrd` compare the variants cast to their discriminants, unless a
+/// variant is given an `order`, and `Debug` looks up the variant's name in a table, if no discriminants are given
+/// explicitly. Otherwise, `Clone` and `Debug` match on every variant, and `Ord` and `PartialOrd` match once to find
+/// the rank of each side.
+///
+/// As with std's derives, the generated impls are marked `#[automatically_derived]`, and their methods other than
+/// `Debug::fmt` are marked `#[inline]`. Lints and tooling then treat them as derived code. In particular, reading a
+/// field in a derived impl does not count as a use of that field, so a field, or a function given as a field option,
+/// that is only used by derived impls is reported as dead code, just as with std's derives.
+///
+/// # Options
+///
+/// Some traits take options in parentheses after the trait name:
+///
+/// - `PartialEq(cheap_first)` compares the fields that are cheapest to compare first, so that a difference in
+///   them is found without comparing the rest. Fields of primitive types, or references, tuples and `Option`s of
+///   them, are compared before the other fields, and otherwise in the order they are declared. As only how soon
+///   a difference is found changes, the result is the same as without the option.
+/// - `PartialEq(structural_match)` also implements the unstable `StructuralPartialEq` marker trait, so that
+///   constants of the type can be used as patterns in a `match`, as with std's `#[derive(PartialEq)]`. The crate
+///   using it must be built with a nightly compiler and enable `#![feature(structural_match)]`. Every field must
+///   be compared by its own `PartialEq`, without being skipped or given another way to be compared.
+/// - `PartialOrd(operators)` also generates `lt`, `le`, `gt` and `ge`, each of which stops at the first field
+///   that decides the result rather than building a full `Option<Ordering>` first.
+/// - `Hash(stable)` hashes the variant of an enum as a `u32` tag, written to the hasher as 4 little-endian bytes,
+///   rather than hashing its [`std::mem::Discriminant`], whose hash may change between compilers and targets. The
+///   tag is the index of the variant, counting from zero, unless given by `hash_tag`. The tag is followed by the
+///   variant's fields, in the order they are declared, each hashed by its own `Hash` implementation. The hash is
+///   then only as portable as those implementations and the hasher used. Structs only hash their fields, and so
+///   don't take this option.
+/// - `Debug(transparent)` formats a struct with exactly one field as that field.
+/// - `Debug(non_exhaustive)` only prints the `pub` fields of a struct, followed by `..`. Only the printed fields
+///   are required to be `Debug`.
+/// - `Debug(with_generics)` prints the full name of a struct, including its generic arguments, as given by
+///   [`std::any::type_name`].
+///
+/// Options for the whole item are given as `name = value` alongside the traits:
+///
+/// - `float = "total"` compares and hashes every field of type `f32` or `f64`, or an `Option` or array of them,
+///   by the floats' total order, as given by `total_cmp`. Such fields can then be used to derive `Eq`, `Ord` and
+///   `Hash`, with `NaN` equal to itself and `-0.0` less than `0.0`. Fields given functions, compared by their
+///   address or ordered by a key are left as they are, but skipped fields are still compared by their total order
+///   in the traits which don't skip them.
+/// - `key = field`, or `key = (field, ...)`, compares and hashes a struct by the named fields alone, in the order
+///   they are named, and ignores the rest in `PartialEq`, `PartialOrd`, `Ord` and `Hash`. The other fields are then
+///   not required to implement those traits, and cannot be given options changing how they are compared.
+/// - `by_variant` compares and hashes an enum by its variant alone, ignoring the fields of each variant.
+///
+/// ```rust
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, Eq, PartialOrd, Ord, Hash, key = (tenant, id))]
+/// struct Record<T> {
+///     id: u64,
+///     tenant: u32,
+///     contents: T,
+/// }
+/// ```
+///
+/// # Field options
+///
+/// Fields take options through a `#[perfect_derive(...)]` attribute of their own:
+///
+/// - `skip(...)` leaves the field out of the listed traits, which may be any of `PartialEq`, `PartialOrd`, `Ord`,
+///   `Hash` and `Debug`. A skipped field is not required to implement the trait. Skipping a field in `PartialEq`
+///   also skips it in `Eq`. So that the traits stay consistent, a field skipped in `PartialEq` must also be
+///   skipped in `Hash`, and `PartialEq`, `PartialOrd` and `Ord` must all skip the same fields.
+/// - `Debug(...)` changes how the field is printed. `rename = "..."` prints a named field under another name,
+///   `redact` prints the string `"<redacted>"` in place of the value, and `with = path::to::fn` formats the field
+///   with a function taking `(&T, &mut Formatter)`. Redacted fields and fields formatted with a function are not
+///   required to be `Debug`.
+/// - `default = expr` gives the value `Default` uses for the field, and `default_with = path::to::fn` gives a
+///   function returning it. Either can be used on the fields of a struct, or of the `#[default]` variant of an
+///   enum. A field with an explicit default is not required to be `Default`.
+/// - `order(...)` changes how `Ord` and `PartialOrd` order the field. `reverse` orders it from greatest to least,
+///   and `by = path::to::fn, key = Type` orders it by the key returned by a function taking `&T`. The field is then
+///   not required to be ordered itself, and `Type` is required to be ordered in its place. This is usually the
+///   key's own type, but for a key borrowing from the field give the type it needs to be ordered, as in
+///   `order(by = as_slice, key = T)` for a function returning `&[T]`.
+///
+///   **A key function must only give equal keys for values which are equal by `PartialEq`.** `Ord` and
+///   `PartialOrd` must agree with `PartialEq`, and sorting and ordered collections such as `BTreeMap` misbehave
+///   otherwise. To order a field by a key which loses information, such as the length of a string, also compare
+///   it by the same key with `with(PartialEq = ..., Hash = ...)`.
+/// - `with(Trait = path::to::fn, ...)` calls a function on the field in place of the method of each listed trait,
+///   and the field is then not required to implement that trait. The function takes the same arguments as the
+///   method, with the field in place of `self`, e.g. `fn(&T, &T) -> bool` for `PartialEq` and
+///   `fn<H: Hasher>(&T, &mut H)` for `Hash`. `with(Debug = ...)` and `with(Default = ...)` are the same as
+///   `Debug(with = ...)` and `default_with = ...`. A field given a function for `PartialEq` must be given one for,
+///   or be skipped in, `Hash`, and must be given one for `PartialOrd` and `Ord` too. When `Ord` and `PartialOrd`
+///   are derived together, `PartialOrd` calls `Ord`, and so a function is only given for `Ord`. The fields of a
+///   union are copied rather than cloned, and so can't be given a function for `Clone`.
+/// - `by_ptr` compares and hashes a pointer field, such as an `Rc<T>` or `Arc<T>`, by the address it points to
+///   rather than by the value there, as with `Rc::ptr_eq`. The value pointed to is then not required to implement
+///   `PartialEq`, `Eq`, `PartialOrd`, `Ord` or `Hash`.
+/// - `PartialEq(priority = N)` sets where the field is compared by `PartialEq(cheap_first)`, with higher
+///   priorities compared first. Fields cheap to compare have a priority of 1, and other fields 0.
+/// - `float = "total"` compares and hashes a single field by the total order of its floats, as with the option
+///   of the same name for the whole item. The field can still be skipped in some of the traits comparing it.
+///
+/// ```rust
+/// # use perfect_derive::perfect_derive;
+/// # use std::time::Instant;
+/// #[perfect_derive(PartialEq, Eq, Hash)]
+/// struct CacheKey {
+///     id: u32,
+///     #[perfect_derive(skip(PartialEq, Hash))]
+///     last_access: Instant,
+/// }
+///
+/// # mod ignore_case {
+/// #     pub fn eq(a: &String, b: &String) -> bool { a.eq_ignore_ascii_case(b) }
+/// #     pub fn hash<H: std::hash::Hasher>(s: &String, state: &mut H) {
+/// #         std::hash::Hash::hash(&s.to_ascii_lowercase(), state)
+/// #     }
+/// # }
+/// #[perfect_derive(PartialEq, Eq, Hash)]
+/// struct Username {
+///     #[perfect_derive(with(PartialEq = ignore_case::eq, Hash = ignore_case::hash))]
+///     name: String,
+/// }
+///
+/// #[perfect_derive(PartialEq, Eq, PartialOrd, Ord)]
+/// struct Task {
+///     #[perfect_derive(order(reverse))]
+///     priority: u8,
+///     id: u32,
+/// }
+///
+/// #[perfect_derive(Default)]
+/// struct RetryPolicy<T> {
+///     #[perfect_derive(default = 3)]
+///     retries: u32,
+///     #[perfect_derive(default_with = Vec::new)]
+///     pending: Vec<T>,
+/// }
+///
+/// #[perfect_derive(Debug)]
+/// struct Credentials<T> {
+///     #[perfect_derive(Debug(rename = "user"))]
+///     username: String,
+///     #[perfect_derive(Debug(redact))]
+///     token: T,
+/// }
+/// ```
+///
+/// Enum variants take options in the same way:
+///
+/// - `order = N` orders the variant by `N` rather than by its discriminant in `Ord` and `PartialOrd`. As with
+///   discriminants, variants without an `order` follow on from the previous variant, starting from zero. No two
+///   variants may have the same order, as `Ord` must agree with `PartialEq`. Orders worked out from integer
+///   literals are checked for this, but orders given by other expressions, such as constants, must be kept unique
+///   by hand.
+/// - `rename = "..."` prints the variant under another name in `Debug`.
+/// - `hash_tag = N` hashes the variant with the tag `N` in `Hash(stable)`. As with `order`, variants without a
+///   `hash_tag` follow on from the previous variant. Tags can be used to keep the hashes of existing variants the
+///   same as variants are added or removed. No two variants may have the same tag. Tags worked out from integer
+///   literals are checked for this, but tags given by other expressions must be kept unique by hand.
+///
+/// ```rust
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// enum Severity {
+///     #[perfect_derive(order = 1, rename = "warn")]
+///     Warning,
+///     #[perfect_derive(order = 0)]
+///     Info,
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn perfect_derive(attr: TokenStream, item: TokenStream) -> TokenStream {
     let traits = parse_macro_input!(attr as DerivedList);
     let obj = parse_macro_input!(item as StructOrEnum);
 
-    TokenStream::from(perfect_macro::impl_traits(traits, obj))
+    TokenStream::from(
+        perfect_macro::impl_traits(traits, obj).unwrap_or_else(syn::Error::into_compile_error),
+    )
 }
+
+/// Uses of the macro which must be rejected, each checked by a `compile_fail` doctest.
+///
+/// A field skipped in `PartialEq` must also be skipped in `Hash`:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, Hash)]
+/// struct Skipped {
+///     id: u32,
+///     #[perfect_derive(skip(PartialEq))]
+///     cache: u32,
+/// }
+/// ```
+///
+/// A field skipped in `Ord` must also be skipped in `PartialEq`:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, Eq, PartialOrd, Ord)]
+/// struct Skipped {
+///     id: u32,
+///     #[perfect_derive(skip(Ord, PartialOrd))]
+///     cache: u32,
+/// }
+/// ```
+///
+/// Only `Copy` and `Clone` can be derived for a union:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(Clone, Copy, PartialEq)]
+/// union Bits {
+///     int: u32,
+///     float: f32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(Default)]
+/// union Bits {
+///     int: u32,
+///     float: f32,
+/// }
+/// ```
+///
+/// A field can't be given a function for `PartialOrd` when `Ord` is derived too, as `PartialOrd` then calls `Ord`:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// # fn reversed(a: &u32, b: &u32) -> Option<std::cmp::Ordering> { b.partial_cmp(a) }
+/// #[perfect_derive(PartialEq, Eq, PartialOrd, Ord)]
+/// struct Reversed {
+///     #[perfect_derive(with(PartialOrd = reversed))]
+///     value: u32,
+/// }
+/// ```
+///
+/// A union is cloned by copying it, so its fields can't be given a function for `Clone`:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// # fn clone_int(int: &u32) -> u32 { *int }
+/// #[perfect_derive(Clone, Copy)]
+/// union Bits {
+///     #[perfect_derive(with(Clone = clone_int))]
+///     int: u32,
+///     float: f32,
+/// }
+/// ```
+///
+/// A variant can only be given each of its options once:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, PartialOrd)]
+/// enum Level {
+///     #[perfect_derive(order = 1, order = 0)]
+///     Low,
+///     High,
+/// }
+/// ```
+///
+/// Each option for the whole item can only be given once, and a key must name at least one field:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, key = id, key = tenant)]
+/// struct Record {
+///     id: u64,
+///     tenant: u32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(PartialEq, key = ())]
+/// struct Record {
+///     id: u64,
+///     tenant: u32,
+/// }
+/// ```
+///
+/// `Default` can't be derived for an enum with no variants:
+///
+/// ```compile_fail
+/// # use perfect_derive::perfect_derive;
+/// #[perfect_derive(Default)]
+/// enum Never {}
+/// ```
+#[cfg(doctest)]
+mod rejected {}
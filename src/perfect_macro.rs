@@ -1,13 +1,18 @@
-use crate::perfect_parsing::{DerivedList, DerivedType, DerivedTypeEnum, StructOrEnum};
-use proc_macro2::{Ident, TokenStream};
+use crate::perfect_parsing::{
+    is_options_attribute, DerivedList, DerivedType, DerivedTypeEnum, FieldDefault, FieldOptions,
+    StructOrEnum, VariantOptions,
+};
+use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use std::collections::HashSet;
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Where;
 use syn::{
-    AttrStyle, Attribute, Fields, FieldsNamed, FieldsUnnamed, GenericParam, Generics, ItemEnum,
-    ItemStruct, Lifetime, PredicateType, TypeParamBound, Variant, WhereClause, WherePredicate,
+    parse_quote, AttrStyle, Attribute, Expr, ExprGroup, ExprLit, ExprParen, ExprUnary, Field,
+    Fields, FieldsNamed, FieldsUnnamed, GenericArgument, GenericParam, Generics, ItemEnum,
+    ItemStruct, Lifetime, Lit, Member, Meta, Path, PathArguments, PredicateType, Token, Type,
+    TypeParamBound, UnOp, Variant, Visibility, WhereClause, WherePredicate,
 };
 
 fn is_attribute_default(a: &Attribute) -> bool {
@@ -47,23 +52,636 @@ fn remove_debug_markers(obj: &mut StructOrEnum) {
     }
 }
 
-pub fn impl_traits(traits: DerivedList, mut obj: StructOrEnum) -> TokenStream {
+fn check_union_support(derived: &DerivedType) -> syn::Result<()> {
+    let reason = match derived.name {
+        DerivedTypeEnum::Copy | DerivedTypeEnum::Clone => return Ok(()),
+        DerivedTypeEnum::Default => "there is no way to choose which field to initialise",
+        _ => "only the code using a union knows which of its fields is active",
+    };
+    Err(syn::Error::new(
+        derived.span,
+        format!(
+            "{:?} cannot be derived for unions, as {}",
+            derived.name, reason
+        ),
+    ))
+}
+
+fn remove_option_markers(obj: &mut StructOrEnum) {
+    for field in obj.fields_mut() {
+        field.attrs.retain(|a| !is_options_attribute(a));
+    }
+    if let StructOrEnum::Enum(e) = obj {
+        for v in e.variants.iter_mut() {
+            v.attrs.retain(|a| !is_options_attribute(a));
+        }
+    }
+}
+
+/// Checks that the options given to enum variants apply to the derived traits.
+fn check_variant_options(traits: &DerivedList, obj: &StructOrEnum) -> syn::Result<()> {
+    let StructOrEnum::Enum(e) = obj else {
+        return Ok(());
+    };
+    let options = e
+        .variants
+        .iter()
+        .map(|v| VariantOptions::from_attrs(&v.attrs))
+        .collect::<syn::Result<Vec<_>>>()?;
+    check_unique_values(
+        e,
+        options
+            .iter()
+            .map(|o| o.order.as_ref().map(|(_, expr)| expr)),
+        "order",
+    )?;
+    check_unique_values(
+        e,
+        options
+            .iter()
+            .map(|o| o.hash_tag.as_ref().map(|(_, expr)| expr)),
+        "hash_tag",
+    )?;
+
+    for v in e.variants.iter() {
+        let options = VariantOptions::from_attrs(&v.attrs)?;
+        if let Some((order, _)) = &options.order {
+            if !traits.contains(DerivedTypeEnum::Ord)
+                && !traits.contains(DerivedTypeEnum::PartialOrd)
+            {
+                return Err(syn::Error::new(
+                    order.span(),
+                    "cannot order a variant, as neither Ord nor PartialOrd is derived",
+                ));
+            }
+        }
+        if let Some(rename) = &options.rename {
+            if !traits.contains(DerivedTypeEnum::Debug) {
+                return Err(syn::Error::new(
+                    rename.span(),
+                    "cannot rename a variant, as Debug is not derived",
+                ));
+            }
+        }
+        if let Some((hash_tag, _)) = &options.hash_tag {
+            if !traits
+                .get(DerivedTypeEnum::Hash)
+                .is_some_and(|derived| derived.has_option("stable"))
+            {
+                return Err(syn::Error::new(
+                    hash_tag.span(),
+                    "cannot give a variant a hash tag, as Hash(stable) is not derived",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of an integer literal, possibly negated, as given for a variant's `order` or `hash_tag`.
+fn literal_value(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse().ok(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_value(expr).map(|value| -value),
+        Expr::Paren(ExprParen { expr, .. }) | Expr::Group(ExprGroup { expr, .. }) => {
+            literal_value(expr)
+        }
+        _ => None,
+    }
+}
+
+/// Checks that no two variants are given the same value, where the values are numbered as
+/// `enum_values` numbers them. Values which depend on expressions other than integer literals can't
+/// be known here, and so aren't checked.
+fn check_unique_values<'a>(
+    e: &ItemEnum,
+    explicit: impl Iterator<Item = Option<&'a Expr>>,
+    option: &str,
+) -> syn::Result<()> {
+    let explicit = explicit.collect::<Vec<_>>();
+    if explicit.iter().all(Option::is_none) {
+        return Ok(());
+    }
+
+    let mut seen = Vec::<(i128, &Ident)>::new();
+    let mut next = Some(0);
+    for (v, expr) in e.variants.iter().zip(explicit) {
+        let value = match expr {
+            Some(expr) => literal_value(expr),
+            None => next,
+        };
+        next = value.and_then(|value| value.checked_add(1));
+        let Some(value) = value else {
+            continue;
+        };
+        if let Some((_, other)) = seen.iter().find(|(seen, _)| *seen == value) {
+            let message = format!(
+                "variant {} is given the {} {}, which variant {} already has",
+                v.ident, option, value, other
+            );
+            return Err(match expr {
+                Some(expr) => syn::Error::new_spanned(expr, message),
+                None => syn::Error::new(v.ident.span(), message),
+            });
+        }
+        seen.push((value, &v.ident));
+    }
+    Ok(())
+}
+
+/// How a field's implementation of a trait is replaced.
+enum Replacement {
+    Skip(Span),
+    With(Span),
+}
+
+fn replacement(options: &FieldOptions, name: DerivedTypeEnum) -> Option<Replacement> {
+    if let Some(skip) = options.skipped(name) {
+        return Some(Replacement::Skip(skip.span));
+    }
+    options.with_span(name).map(Replacement::With)
+}
+
+/// Whether a field is skipped or given functions for any of the traits comparing it.
+fn has_comparison_options(options: &FieldOptions) -> bool {
+    FieldOptions::COMPARING
+        .iter()
+        .any(|name| replacement(options, *name).is_some())
+}
+
+/// Whether a field is given functions for any of the traits comparing it.
+fn has_comparison_functions(options: &FieldOptions) -> bool {
+    FieldOptions::COMPARING
+        .iter()
+        .any(|name| options.with_fn(*name).is_some())
+}
+
+/// Applies the options given for the whole item to each of the fields they affect, as if the fields
+/// had been given them. Fields with options of their own overriding them are left as they are.
+fn apply_container_options(traits: &DerivedList, obj: &mut StructOrEnum) {
+    if let Some(float) = &traits.1.float {
+        for field in obj.fields_mut() {
+            let options = FieldOptions::of(field);
+            if is_float_type(&field.ty)
+                && options.float.is_none()
+                && options.by_ptr.is_none()
+                && options
+                    .order
+                    .as_ref()
+                    .is_none_or(|order| order.by.is_none())
+                && !has_comparison_functions(&options)
+            {
+                field
+                    .attrs
+                    .push(parse_quote! { #[perfect_derive(float = #float)] });
+            }
+        }
+    }
+
+    // Fields outside of the key are skipped by every trait comparing them
+    let skipped = key_skipped_traits(traits);
+    match obj {
+        StructOrEnum::Struct(s) => {
+            let Some((_, key)) = &traits.1.key else {
+                return;
+            };
+            let members = s.fields.members().collect::<Vec<_>>();
+            for (field, member) in s.fields.iter_mut().zip(members) {
+                if !key.contains(&member) {
+                    field
+                        .attrs
+                        .push(parse_quote! { #[perfect_derive(skip(#(#skipped),*))] });
+                }
+            }
+        }
+        StructOrEnum::Enum(_) if traits.1.by_variant.is_some() => {
+            for field in obj.fields_mut() {
+                field
+                    .attrs
+                    .push(parse_quote! { #[perfect_derive(skip(#(#skipped),*))] });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The derived traits which ignore the fields outside of the key given by `key` or `by_variant`.
+fn key_skipped_traits(traits: &DerivedList) -> Vec<Ident> {
+    [
+        DerivedTypeEnum::PartialEq,
+        DerivedTypeEnum::PartialOrd,
+        DerivedTypeEnum::Ord,
+        DerivedTypeEnum::Hash,
+    ]
+    .into_iter()
+    .filter(|name| traits.contains(*name))
+    .map(|name| Ident::new(&format!("{:?}", name), Span::call_site()))
+    .collect()
+}
+
+/// Checks that the options given for the whole item apply to it and the derived traits.
+fn check_container_options(traits: &DerivedList, obj: &StructOrEnum) -> syn::Result<()> {
+    let (option, key): (_, &[Member]) = match (&traits.1.key, &traits.1.by_variant, obj) {
+        (Some(_), Some(option), _) => {
+            return Err(syn::Error::new(
+                option.span(),
+                "key and by_variant cannot be used together",
+            ))
+        }
+        (Some((option, key)), None, StructOrEnum::Struct(_)) => (option, key),
+        (Some((option, _)), None, _) => return Err(syn::Error::new(
+            option.span(),
+            "key is only supported on structs, use by_variant to compare enums by their variants",
+        )),
+        (None, Some(option), StructOrEnum::Enum(_)) => (option, &[]),
+        (None, Some(option), _) => {
+            return Err(syn::Error::new(
+                option.span(),
+                "by_variant is only supported on enums",
+            ))
+        }
+        (None, None, _) => return Ok(()),
+    };
+
+    if key_skipped_traits(traits).is_empty() {
+        return Err(syn::Error::new(
+            option.span(),
+            format!(
+                "{} has no effect, as none of PartialEq, Eq, PartialOrd, Ord or Hash is derived",
+                option
+            ),
+        ));
+    }
+
+    let members = match obj {
+        StructOrEnum::Struct(s) => s.fields.members().collect(),
+        _ => Vec::new(),
+    };
+    for (i, member) in key.iter().enumerate() {
+        if !members.contains(member) {
+            return Err(syn::Error::new(
+                member.span(),
+                "the key must only name fields of the struct",
+            ));
+        }
+        if key[..i].contains(member) {
+            return Err(syn::Error::new(
+                member.span(),
+                "a field can only be named once in the key",
+            ));
+        }
+    }
+
+    for (i, field) in obj.fields().into_iter().enumerate() {
+        let in_key = members.get(i).is_some_and(|member| key.contains(member));
+        let options = FieldOptions::from_attrs(&field.attrs)?;
+        let customised = FieldOptions::COMPARING
+            .iter()
+            .any(|name| matches!(replacement(&options, *name), Some(Replacement::With(_))))
+            || options.by_ptr.is_some()
+            || options.float.is_some()
+            || options.order.is_some();
+        if in_key
+            && FieldOptions::COMPARING
+                .iter()
+                .any(|name| options.skips(*name))
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                "a field in the key cannot be skipped",
+            ));
+        }
+        if !in_key && customised {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!(
+                    "only fields in the key can change how they are compared, as {} ignores the rest",
+                    option
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the options given to fields apply to the derived traits, and that the fields skipped
+/// by each derived trait keep the traits consistent with each other, e.g. values which are equal must
+/// hash the same, so a field ignored by `PartialEq` must also be ignored by `Hash`.
+fn check_field_options(traits: &DerivedList, obj: &StructOrEnum) -> syn::Result<()> {
+    use DerivedTypeEnum::*;
+
+    // Each pair is (a, b, whether b may ignore fields a doesn't)
+    let consistent = [
+        (PartialEq, Hash, true),
+        (PartialEq, Ord, false),
+        (PartialEq, PartialOrd, false),
+        (PartialOrd, Ord, false),
+    ];
+
+    if let (StructOrEnum::Enum(e), true) = (obj, traits.contains(Default)) {
+        for v in e
+            .variants
+            .iter()
+            .filter(|v| !v.attrs.iter().any(is_attribute_default))
+        {
+            for field in v.fields.iter() {
+                if let Some(default) = FieldOptions::from_attrs(&field.attrs)?.default {
+                    return Err(syn::Error::new(
+                        default.span(),
+                        "only fields of the default variant can be given a default",
+                    ));
+                }
+            }
+        }
+    }
+
+    for field in obj.fields() {
+        let options = FieldOptions::from_attrs(&field.attrs)?;
+        for derived in options.skip.iter() {
+            if !traits.contains(derived.name) {
+                return Err(syn::Error::new(
+                    derived.span,
+                    format!(
+                        "cannot skip a field in {:?}, as it is not derived",
+                        derived.name
+                    ),
+                ));
+            }
+        }
+        for (derived, _) in options.with.iter() {
+            if !traits.contains(derived.name) {
+                return Err(syn::Error::new(
+                    derived.span,
+                    format!(
+                        "cannot give a function for {:?}, as it is not derived",
+                        derived.name
+                    ),
+                ));
+            }
+            if options.skips(derived.name) {
+                return Err(syn::Error::new(
+                    derived.span,
+                    format!(
+                        "a field skipped in {:?} cannot also be given a function for it",
+                        derived.name
+                    ),
+                ));
+            }
+            if derived.name == PartialOrd && traits.contains(Ord) {
+                return Err(syn::Error::new(
+                    derived.span,
+                    "PartialOrd is implemented by Ord when both are derived, so only give a function for Ord",
+                ));
+            }
+            if derived.name == Clone && matches!(obj, StructOrEnum::Union(_)) {
+                return Err(syn::Error::new(
+                    derived.span,
+                    "a union is cloned by copying it, so its fields cannot be given a function for Clone",
+                ));
+            }
+        }
+
+        if let Some(span) = options.debug.span {
+            if !traits.contains(Debug) {
+                return Err(syn::Error::new(
+                    span,
+                    "cannot customise how a field is printed, as Debug is not derived",
+                ));
+            }
+            if let Some(skip) = options.skipped(Debug) {
+                return Err(syn::Error::new(
+                    skip.span,
+                    "a field skipped in Debug cannot also customise how it is printed",
+                ));
+            }
+        }
+        if let Some((priority, _)) = &options.priority {
+            if !traits
+                .get(PartialEq)
+                .is_some_and(|derived| derived.has_option("cheap_first"))
+            {
+                return Err(syn::Error::new(
+                    priority.span(),
+                    "a priority has no effect, as PartialEq(cheap_first) is not derived",
+                ));
+            }
+            if let Some(skip) = options.skipped(PartialEq) {
+                return Err(syn::Error::new(
+                    skip.span,
+                    "a field skipped in PartialEq cannot also be given a priority",
+                ));
+            }
+        }
+        if let Some(default) = &options.default {
+            if !traits.contains(Default) {
+                return Err(syn::Error::new(
+                    default.span(),
+                    "cannot give a field a default, as Default is not derived",
+                ));
+            }
+        }
+        if let Some(by_ptr) = &options.by_ptr {
+            if !FieldOptions::COMPARING
+                .iter()
+                .any(|name| traits.contains(*name))
+            {
+                return Err(syn::Error::new(
+                    by_ptr.span(),
+                    "cannot compare a field by its address, as none of PartialEq, Eq, PartialOrd, Ord or Hash is derived",
+                ));
+            }
+            if options.order.is_some() || has_comparison_options(&options) {
+                return Err(syn::Error::new(
+                    by_ptr.span(),
+                    "a field compared by its address cannot also be skipped, ordered or given functions in the traits comparing it",
+                ));
+            }
+        }
+        if let Some(float) = &options.float {
+            if !is_float_type(&field.ty) {
+                return Err(syn::Error::new(
+                    float.span(),
+                    "only fields of type f32 or f64, or Options or arrays of them, can be compared by their total order",
+                ));
+            }
+            if !FieldOptions::COMPARING
+                .iter()
+                .any(|name| traits.contains(*name))
+            {
+                return Err(syn::Error::new(
+                    float.span(),
+                    "cannot compare a field by its total order, as none of PartialEq, Eq, PartialOrd, Ord or Hash is derived",
+                ));
+            }
+            if has_comparison_functions(&options)
+                || options.by_ptr.is_some()
+                || options
+                    .order
+                    .as_ref()
+                    .is_some_and(|order| order.by.is_some())
+            {
+                return Err(syn::Error::new(
+                    float.span(),
+                    "a field compared by its total order cannot also be compared some other way",
+                ));
+            }
+        }
+        if let Some(order) = &options.order {
+            if !traits.contains(Ord) && !traits.contains(PartialOrd) {
+                return Err(syn::Error::new(
+                    order.option.span(),
+                    "cannot order a field, as neither Ord nor PartialOrd is derived",
+                ));
+            }
+            if let Some(skip) = options.skipped(Ord).or(options.skipped(PartialOrd)) {
+                return Err(syn::Error::new(
+                    skip.span,
+                    "a field skipped in the ordering cannot also change how it is ordered",
+                ));
+            }
+            if let Some(span) = options.with_span(Ord).or(options.with_span(PartialOrd)) {
+                return Err(syn::Error::new(
+                    span,
+                    "a field ordered with a function cannot also change how it is ordered",
+                ));
+            }
+        }
+        if let (Some(redact), Some(_)) = (&options.debug.redact, &options.debug.with) {
+            return Err(syn::Error::new(
+                redact.span(),
+                "a redacted field cannot also be formatted with a function",
+            ));
+        }
+        if let (None, Some(rename)) = (&field.ident, &options.debug.rename) {
+            return Err(syn::Error::new(
+                rename.span(),
+                "only named fields can be renamed",
+            ));
+        }
+
+        // A `PartialOrd` delegating to `Ord` uses the function given for `Ord`
+        let replacement = |name| match replacement(&options, name) {
+            None if name == PartialOrd && traits.contains(Ord) => {
+                options.with_span(Ord).map(Replacement::With)
+            }
+            replaced => replaced,
+        };
+        for (a, b, lenient) in consistent {
+            if !traits.contains(a) || !traits.contains(b) {
+                continue;
+            }
+            let error = match (replacement(a), replacement(b)) {
+                (None, None) | (Some(Replacement::Skip(_)), Some(Replacement::Skip(_))) => None,
+                (Some(Replacement::With(_)), Some(Replacement::With(_))) => None,
+                // `b` can always be less precise than `a`
+                (None, Some(_)) | (Some(Replacement::With(_)), Some(Replacement::Skip(_)))
+                    if lenient =>
+                {
+                    None
+                }
+                (Some(Replacement::Skip(span)), _) => Some((
+                    span,
+                    format!("a field skipped in {:?} must also be skipped in {:?}", a, b),
+                )),
+                (Some(Replacement::With(span)), _) if lenient => Some((
+                    span,
+                    format!(
+                        "a field given a function for {:?} must also be given a function for, or be skipped in, {:?}",
+                        a, b
+                    ),
+                )),
+                (Some(Replacement::With(span)), _) => Some((
+                    span,
+                    format!(
+                        "a field given a function for {:?} must also be given a function for {:?}",
+                        a, b
+                    ),
+                )),
+                (None, Some(Replacement::Skip(span))) => Some((
+                    span,
+                    format!("a field skipped in {:?} must also be skipped in {:?}", b, a),
+                )),
+                (None, Some(Replacement::With(span))) => Some((
+                    span,
+                    format!(
+                        "a field given a function for {:?} must also be given a function for {:?}",
+                        b, a
+                    ),
+                )),
+            };
+            if let Some((span, message)) = error {
+                return Err(syn::Error::new(span, message));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn impl_traits(traits: DerivedList, mut obj: StructOrEnum) -> syn::Result<TokenStream> {
     let mut output = quote! {};
 
     let mut already_derived = HashSet::new();
-    for derived in traits.0 {
-        if already_derived.contains(&derived.name) {
-            panic!("cannot derive {:?} twice", derived.name)
+    for derived in traits.0.iter() {
+        if !already_derived.insert(derived.name) {
+            return Err(syn::Error::new(
+                derived.span,
+                format!("cannot derive {:?} twice", derived.name),
+            ));
+        }
+    }
+
+    check_container_options(&traits, &obj)?;
+    check_field_options(&traits, &obj)?;
+    check_variant_options(&traits, &obj)?;
+    apply_container_options(&traits, &mut obj);
+
+    for derived in traits.0.iter() {
+        if let StructOrEnum::Union(_) = &obj {
+            check_union_support(derived)?;
         }
-        already_derived.insert(derived.name);
 
-        add_type_impl(&mut output, &derived, &obj);
+        add_type_impl(&mut output, derived, &traits, &obj)?;
+    }
+
+    // As with std's derive, allow constants to be used as patterns. Requires `#![feature(structural_match)]`
+    if let Some(structural) = traits
+        .get(DerivedTypeEnum::PartialEq)
+        .and_then(|derived| derived.option("structural_match"))
+    {
+        // The fields are checked once the options for the whole item have been applied to them
+        if !obj
+            .fields()
+            .into_iter()
+            .all(|field| is_field_bounded(field, DerivedTypeEnum::PartialEq))
+        {
+            return Err(syn::Error::new(
+                structural.span(),
+                "PartialEq(structural_match) requires every field to be compared by its own PartialEq",
+            ));
+        }
+        for (cfg, selected) in cfg_generic_combinations(&obj) {
+            add_impl(
+                &mut output,
+                &selected,
+                cfg,
+                parse_quote! { ::core::marker::StructuralPartialEq },
+                selected.generics().where_clause,
+                quote! {},
+            );
+        }
     }
 
     // If we derived Default, we need to remove any default markers from enums
     if already_derived.contains(&DerivedTypeEnum::Default) {
         remove_debug_markers(&mut obj);
     }
+    remove_option_markers(&mut obj);
 
     output = quote! {
         #obj
@@ -71,7 +689,7 @@ pub fn impl_traits(traits: DerivedList, mut obj: StructOrEnum) -> TokenStream {
         #output
     };
 
-    output
+    Ok(output)
 }
 
 enum IdentOrLifetime {
@@ -116,21 +734,179 @@ fn remove_defaults(generics: &mut Generics) {
     }
 }
 
-/*fn remove_attrs(generics: &mut Generics) {
-    for generic in generics.params.iter_mut() {
-        match generic {
-            GenericParam::Type(t) => {
-                t.attrs = vec![];
-            }
-            GenericParam::Lifetime(lt) => {
-                lt.attrs = vec![];
-            }
-            GenericParam::Const(c) => {
-                c.attrs = vec![];
-            }
+fn generic_param_attrs(generic: &mut GenericParam) -> &mut Vec<Attribute> {
+    match generic {
+        GenericParam::Type(t) => &mut t.attrs,
+        GenericParam::Lifetime(lt) => &mut lt.attrs,
+        GenericParam::Const(c) => &mut c.attrs,
+    }
+}
+
+fn is_attribute_cfg(a: &Attribute) -> bool {
+    a.path().is_ident("cfg")
+}
+
+/// The predicates of each of the `#[cfg]` attributes given.
+fn cfg_predicates(attrs: &[Attribute]) -> Vec<TokenStream> {
+    attrs
+        .iter()
+        .filter(|a| is_attribute_cfg(a))
+        .filter_map(|a| match &a.meta {
+            Meta::List(l) => Some(l.tokens.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether any of the given generic parameters is named in the tokens.
+fn names_any(tokens: TokenStream, params: &[IdentOrLifetime]) -> bool {
+    let mut after_quote = false;
+    for token in tokens {
+        let found = match &token {
+            TokenTree::Group(group) => names_any(group.stream(), params),
+            TokenTree::Ident(ident) => params.iter().any(|param| match param {
+                IdentOrLifetime::Ident(param) => !after_quote && param == ident,
+                IdentOrLifetime::Lifetime(param) => after_quote && param.ident == *ident,
+            }),
+            _ => false,
+        };
+        if found {
+            return true;
         }
+        after_quote = matches!(&token, TokenTree::Punct(p) if p.as_char() == '\'');
     }
-}*/
+    false
+}
+
+fn retain_fields(fields: &mut Fields, keep: impl Fn(&Field) -> bool) {
+    match fields {
+        Fields::Named(named) => {
+            named.named = std::mem::take(&mut named.named)
+                .into_iter()
+                .filter(|field| keep(field))
+                .collect()
+        }
+        Fields::Unnamed(unnamed) => {
+            unnamed.unnamed = std::mem::take(&mut unnamed.unnamed)
+                .into_iter()
+                .filter(|field| keep(field))
+                .collect()
+        }
+        Fields::Unit => {}
+    }
+}
+
+/// Generic arguments can't carry attributes, so a `#[cfg]` on a generic parameter can't be forwarded
+/// to the type we are implementing on. Instead we enumerate every combination of the conditional
+/// parameters being present or absent, giving the predicate selecting each combination and the item as
+/// it is in that combination. The absent parameters are removed, along with the fields naming them and
+/// the variants and fields whose `#[cfg]`s include all of those of an absent parameter, so that the
+/// bounds and bodies of each impl only use what is present.
+fn cfg_generic_combinations(obj: &StructOrEnum) -> Vec<(Option<TokenStream>, StructOrEnum)> {
+    let generics = obj.generics();
+    let mut conditional = Vec::new();
+    for (i, mut generic) in generics.params.clone().into_iter().enumerate() {
+        let predicates = cfg_predicates(generic_param_attrs(&mut generic));
+        if !predicates.is_empty() {
+            conditional.push((i, predicates));
+        }
+    }
+
+    if conditional.is_empty() {
+        return vec![(None, obj.clone())];
+    }
+
+    (0..1usize << conditional.len())
+        .map(|mask| {
+            let is_present = |bit: usize| mask & (1 << bit) != 0;
+            let selector = conditional
+                .iter()
+                .enumerate()
+                .map(|(bit, (_, predicates))| {
+                    if is_present(bit) {
+                        quote! { all(#(#predicates),*) }
+                    } else {
+                        quote! { not(all(#(#predicates),*)) }
+                    }
+                });
+            let selector = quote! { all(#(#selector),*) };
+
+            let absent = conditional
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| !is_present(*bit))
+                .map(|(_, conditional)| conditional)
+                .collect::<Vec<_>>();
+            let mut absent_generics = generics.clone();
+            absent_generics.params = generics
+                .params
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| absent.iter().any(|(j, _)| i == j))
+                .map(|(_, generic)| generic.clone())
+                .collect();
+            let absent_names = extract_idents(&absent_generics);
+
+            // Something whose `#[cfg]`s include those of an absent parameter is absent alongside it
+            let is_cfg_absent = |attrs: &[Attribute]| {
+                let predicates = cfg_predicates(attrs)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                absent.iter().any(|(_, absent)| {
+                    absent
+                        .iter()
+                        .all(|predicate| predicates.contains(&predicate.to_string()))
+                })
+            };
+            let keep_field = |field: &Field| {
+                !is_cfg_absent(&field.attrs)
+                    && !names_any(field.ty.to_token_stream(), &absent_names)
+            };
+
+            let mut selected = generics.clone();
+            selected.params = generics
+                .params
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !absent.iter().any(|(j, _)| i == j))
+                .map(|(_, generic)| {
+                    let mut generic = generic.clone();
+                    generic_param_attrs(&mut generic).retain(|a| !is_attribute_cfg(a));
+                    generic
+                })
+                .collect();
+
+            let mut obj = obj.clone();
+            match &mut obj {
+                StructOrEnum::Struct(s) => {
+                    s.generics = selected;
+                    retain_fields(&mut s.fields, keep_field);
+                }
+                StructOrEnum::Enum(e) => {
+                    e.generics = selected;
+                    e.variants = std::mem::take(&mut e.variants)
+                        .into_iter()
+                        .filter(|v| !is_cfg_absent(&v.attrs))
+                        .map(|mut v| {
+                            retain_fields(&mut v.fields, keep_field);
+                            v
+                        })
+                        .collect();
+                }
+                StructOrEnum::Union(u) => {
+                    u.generics = selected;
+                    u.fields.named = std::mem::take(&mut u.fields.named)
+                        .into_iter()
+                        .filter(|field| keep_field(field))
+                        .collect();
+                }
+            }
+
+            (Some(selector), obj)
+        })
+        .collect()
+}
 
 /*fn remove_bounds(generics: &mut Generics) {
     for generic in generics.params.iter_mut() {
@@ -148,75 +924,156 @@ fn remove_defaults(generics: &mut Generics) {
     }
 }*/
 
-fn add_type_impl(output: &mut TokenStream, trait_to_impl: &DerivedType, obj: &StructOrEnum) {
+fn add_type_impl(
+    output: &mut TokenStream,
+    trait_to_impl: &DerivedType,
+    traits: &DerivedList,
+    obj: &StructOrEnum,
+) -> syn::Result<()> {
+    for (cfg, obj) in cfg_generic_combinations(obj) {
+        let trait_impl = gen_type_impl_body(trait_to_impl, traits, &obj)?;
+        let gen_where =
+            augment_where_clause(obj.generics().where_clause, trait_to_impl, traits, &obj)?;
+
+        add_impl(
+            output,
+            &obj,
+            cfg,
+            trait_to_impl.path(),
+            Some(gen_where),
+            trait_impl,
+        );
+    }
+
+    Ok(())
+}
+
+fn add_impl(
+    output: &mut TokenStream,
+    obj: &StructOrEnum,
+    cfg: Option<TokenStream>,
+    trait_ident: Path,
+    gen_where: Option<WhereClause>,
+    trait_impl: TokenStream,
+) {
     let ident = obj.ident();
     let generics = obj.generics();
+
+    let cfg = cfg.map(|predicate| quote! { #[cfg(#predicate)] });
     let gen_names = extract_idents(&generics);
     let gen_lt = generics.lt_token;
     let gen_gt = generics.gt_token;
 
     let mut impl_generic_introduction = generics.clone();
     remove_defaults(&mut impl_generic_introduction);
-    //remove_attrs(&mut impl_generic_introduction);
     //remove_bounds(&mut impl_generic_introduction);
     let gen_params = impl_generic_introduction.params;
-    let gen_where = augment_where_clause(generics.where_clause, trait_to_impl, obj);
-
-    let trait_ident = trait_to_impl.path();
-
-    let trait_impl = gen_type_impl_body(trait_to_impl, obj);
 
     *output = quote! {
         #output
 
+        #cfg
+        #[automatically_derived]
+        #[allow(unused_qualifications)]
         impl #gen_lt #gen_params #gen_gt #trait_ident for #ident #gen_lt #(#gen_names),* #gen_gt #gen_where {
             #trait_impl
         }
     };
 }
 
-fn get_debug_enum_marker(enum_item: &ItemEnum) -> &Variant {
-    let default_variants = enum_item
+fn get_debug_enum_marker(enum_item: &ItemEnum) -> syn::Result<&Variant> {
+    let mut default_variants = enum_item
         .variants
         .iter()
-        .filter(|v| v.attrs.iter().any(is_attribute_default))
-        .collect::<Vec<_>>();
-    assert!(
-        !default_variants.is_empty(),
-        "one enum variant must be marked as default"
-    );
-    assert_eq!(
-        default_variants.len(),
-        1,
-        "only one enum variant may be marked as default"
-    );
-    return default_variants.first().unwrap();
+        .filter(|v| v.attrs.iter().any(is_attribute_default));
+    let Some(default_variant) = default_variants.next() else {
+        let message = if enum_item.variants.is_empty() {
+            "Default cannot be derived for an enum with no variants, as it can never be constructed"
+        } else {
+            "one enum variant must be marked as default"
+        };
+        return Err(syn::Error::new(enum_item.ident.span(), message));
+    };
+    if let Some(other) = default_variants.next() {
+        return Err(syn::Error::new(
+            other.ident.span(),
+            "only one enum variant may be marked as default",
+        ));
+    }
+    Ok(default_variant)
 }
 
 fn augment_where_clause(
     clause: Option<WhereClause>,
     trait_to_impl: &DerivedType,
+    traits: &DerivedList,
     obj: &StructOrEnum,
-) -> WhereClause {
+) -> syn::Result<WhereClause> {
+    let bound_trait = match (trait_to_impl.name, obj) {
+        // A `PartialOrd` delegating to `Ord` needs the same bounds as `Ord`
+        (DerivedTypeEnum::PartialOrd, _) if traits.contains(DerivedTypeEnum::Ord) => {
+            DerivedTypeEnum::Ord
+        }
+        // A union can only be cloned by copying it
+        (DerivedTypeEnum::Clone, StructOrEnum::Union(_)) => DerivedTypeEnum::Copy,
+        (name, _) => name,
+    };
+
     let extra = match (&trait_to_impl.name, obj) {
         (DerivedTypeEnum::Default, StructOrEnum::Enum(e)) => {
-            let variant = get_debug_enum_marker(e);
+            let variant = get_debug_enum_marker(e)?;
             variant
                 .fields
                 .iter()
+                .filter(|f| FieldOptions::of(f).default.is_none())
                 .map(|f| f.ty.clone())
                 .collect::<Vec<_>>()
         }
-        (_, StructOrEnum::Struct(s)) => s.fields.iter().map(|f| f.ty.clone()).collect(),
-        (_, StructOrEnum::Enum(e)) => e
-            .variants
+        (DerivedTypeEnum::Default, StructOrEnum::Struct(s)) => s
+            .fields
             .iter()
-            .flat_map(|v| v.fields.iter().map(|f| f.ty.clone()))
+            .filter(|f| FieldOptions::of(f).default.is_none())
+            .map(|f| f.ty.clone())
+            .collect(),
+        (DerivedTypeEnum::Debug, StructOrEnum::Struct(_) | StructOrEnum::Enum(_)) => obj
+            .fields()
+            .into_iter()
+            .filter(|f| is_debug_bounded(f, trait_to_impl))
+            .map(|f| f.ty.clone())
             .collect(),
+        // A field ordered by a key function is bounded by the type of its key, if one is given
+        (
+            DerivedTypeEnum::Ord | DerivedTypeEnum::PartialOrd,
+            StructOrEnum::Struct(_) | StructOrEnum::Enum(_),
+        ) => obj
+            .fields()
+            .into_iter()
+            .filter(|f| is_field_bounded(f, bound_trait))
+            .map(
+                |f| match FieldOptions::of(f).order.and_then(|order| order.key) {
+                    Some(key) => key,
+                    None => f.ty.clone(),
+                },
+            )
+            .collect(),
+        (_, StructOrEnum::Struct(_) | StructOrEnum::Enum(_)) => obj
+            .fields()
+            .into_iter()
+            .filter(|f| is_field_bounded(f, bound_trait))
+            .map(|f| f.ty.clone())
+            .collect(),
+        (DerivedTypeEnum::Clone, StructOrEnum::Union(_)) => vec![parse_quote! { Self }],
+        (_, StructOrEnum::Union(u)) => u.fields.named.iter().map(|f| f.ty.clone()).collect(),
+    };
+
+    let bound_trait = DerivedType {
+        name: bound_trait,
+        span: trait_to_impl.span,
+        options: Vec::new(),
     };
 
     let mut bounds = Punctuated::new();
-    bounds.push(TypeParamBound::Trait(trait_to_impl.get_trait()));
+    bounds.push(TypeParamBound::Trait(bound_trait.get_trait()));
     let extra = extra.into_iter().map(|bounded_ty| {
         WherePredicate::Type(PredicateType {
             lifetimes: None,
@@ -234,12 +1091,12 @@ fn augment_where_clause(
         predicates.push(predicate)
     }
 
-    WhereClause {
+    Ok(WhereClause {
         where_token: clause.map(|c| c.where_token).unwrap_or(Where {
             span: trait_to_impl.span,
         }),
         predicates,
-    }
+    })
 }
 
 fn get_named_idents(names: &FieldsNamed) -> Vec<Ident> {
@@ -265,10 +1122,6 @@ fn get_named_idents_suffix(names: &FieldsNamed, suffix: &str) -> Vec<Ident> {
         .collect::<Vec<_>>()
 }
 
-fn get_unnamed_idents(unnamed: &FieldsUnnamed) -> Vec<Ident> {
-    get_unnamed_idents_prefix(unnamed, "v")
-}
-
 fn get_unnamed_idents_prefix(unnamed: &FieldsUnnamed, prefix: &str) -> Vec<Ident> {
     unnamed
         .unnamed
@@ -278,54 +1131,182 @@ fn get_unnamed_idents_prefix(unnamed: &FieldsUnnamed, prefix: &str) -> Vec<Ident
         .collect::<Vec<_>>()
 }
 
-fn gen_type_impl_body(trait_to_impl: &DerivedType, obj: &StructOrEnum) -> TokenStream {
-    match (&trait_to_impl.name, obj) {
+fn gen_type_impl_body(
+    trait_to_impl: &DerivedType,
+    traits: &DerivedList,
+    obj: &StructOrEnum,
+) -> syn::Result<TokenStream> {
+    Ok(match (&trait_to_impl.name, obj) {
         (DerivedTypeEnum::Copy, _) => quote!(),
         (DerivedTypeEnum::Eq, _) => quote!(),
+        (DerivedTypeEnum::Default, StructOrEnum::Enum(e)) => default_enum(e)?,
+        (_, StructOrEnum::Enum(e)) if e.variants.is_empty() => uninhabited_enum(trait_to_impl),
+        (DerivedTypeEnum::Clone, StructOrEnum::Union(_)) => clone_from_copy(),
+        // Like std, only rely on `Copy` when it can't require more than `Clone` does, and when no
+        // field is cloned by a function of its own
+        (DerivedTypeEnum::Clone, _)
+            if traits.contains(DerivedTypeEnum::Copy)
+                && obj.generics().type_params().next().is_none()
+                && !obj
+                    .fields()
+                    .into_iter()
+                    .any(|field| FieldOptions::of(field).replaces(DerivedTypeEnum::Clone)) =>
+        {
+            clone_from_copy()
+        }
         (DerivedTypeEnum::Clone, StructOrEnum::Struct(s)) => clone_struct(s),
         (DerivedTypeEnum::Clone, StructOrEnum::Enum(e)) => clone_enum(e),
-        (DerivedTypeEnum::PartialEq, StructOrEnum::Struct(s)) => peq_struct(s),
-        (DerivedTypeEnum::PartialEq, StructOrEnum::Enum(e)) => peq_enum(e),
-        (DerivedTypeEnum::Ord, StructOrEnum::Struct(s)) => ord_struct(s),
-        (DerivedTypeEnum::Ord, StructOrEnum::Enum(e)) => ord_enum(e),
-        (DerivedTypeEnum::PartialOrd, StructOrEnum::Struct(s)) => pord_struct(s),
-        (DerivedTypeEnum::PartialOrd, StructOrEnum::Enum(e)) => pord_enum(e),
-        (DerivedTypeEnum::Hash, StructOrEnum::Struct(s)) => hash_struct(s),
+        (DerivedTypeEnum::PartialEq, StructOrEnum::Struct(s)) => {
+            peq_struct(s, trait_to_impl, traits)
+        }
+        (DerivedTypeEnum::PartialEq, StructOrEnum::Enum(e)) if is_fieldless(e) => {
+            peq_fieldless_enum()
+        }
+        (DerivedTypeEnum::PartialEq, StructOrEnum::Enum(e)) => peq_enum(e, trait_to_impl),
+        (DerivedTypeEnum::Ord, StructOrEnum::Struct(s)) => ord_struct(s, traits),
+        (DerivedTypeEnum::Ord, StructOrEnum::Enum(e)) => ord_enum(e, traits),
+        (DerivedTypeEnum::PartialOrd, _) => pord(trait_to_impl, traits, obj),
+        (DerivedTypeEnum::Hash, StructOrEnum::Struct(_)) if trait_to_impl.has_option("stable") => {
+            return Err(syn::Error::new(
+                trait_to_impl.options[0].span(),
+                "Hash(stable) is only supported on enums, as structs only hash their fields",
+            ))
+        }
+        (DerivedTypeEnum::Hash, StructOrEnum::Struct(s)) => hash_struct(s, traits),
+        (DerivedTypeEnum::Hash, StructOrEnum::Enum(e)) if trait_to_impl.has_option("stable") => {
+            hash_stable_enum(e)
+        }
+        (DerivedTypeEnum::Hash, StructOrEnum::Enum(e)) if is_fieldless(e) => hash_fieldless_enum(),
         (DerivedTypeEnum::Hash, StructOrEnum::Enum(e)) => hash_enum(e),
-        (DerivedTypeEnum::Debug, StructOrEnum::Struct(s)) => debug_struct(s),
-        (DerivedTypeEnum::Debug, StructOrEnum::Enum(e)) => debug_enum(e),
+        (DerivedTypeEnum::Debug, StructOrEnum::Struct(s)) => debug_struct(s, trait_to_impl)?,
+        (DerivedTypeEnum::Debug, StructOrEnum::Enum(_)) if !trait_to_impl.options.is_empty() => {
+            return Err(syn::Error::new(
+                trait_to_impl.options[0].span(),
+                format!(
+                    "Debug({}) is only supported on structs",
+                    trait_to_impl.options[0]
+                ),
+            ))
+        }
+        (DerivedTypeEnum::Debug, StructOrEnum::Enum(e)) if is_fieldless(e) => {
+            debug_fieldless_enum(e, traits)
+        }
+        (DerivedTypeEnum::Debug, StructOrEnum::Enum(e)) => debug_enum(e, trait_to_impl),
         (DerivedTypeEnum::Default, StructOrEnum::Struct(s)) => default_struct(s),
-        (DerivedTypeEnum::Default, StructOrEnum::Enum(e)) => default_enum(e),
-    }
+        (_, StructOrEnum::Union(_)) => {
+            unreachable!("only Copy and Clone can be derived for unions")
+        }
+    })
 }
 
-fn clone_struct(s: &ItemStruct) -> TokenStream {
-    match &s.fields {
-        Fields::Named(names) => {
-            let idents = get_named_idents(names);
-
-            quote! {
-                fn clone(&self) -> Self {
-                    let Self{ #(#idents),* } = self;
-                    Self{ #(#idents : #idents.clone()),* }
-                }
-            }
+/// An enum without variants can never be constructed, so none of its methods can be called.
+fn uninhabited_enum(trait_to_impl: &DerivedType) -> TokenStream {
+    let signatures = match trait_to_impl.name {
+        DerivedTypeEnum::Clone => vec![quote! { #[inline] fn clone(&self) -> Self }],
+        DerivedTypeEnum::PartialEq => vec![quote! { #[inline] fn eq(&self, _: &Self) -> bool }],
+        DerivedTypeEnum::Ord => {
+            vec![quote! { #[inline] fn cmp(&self, _: &Self) -> std::cmp::Ordering }]
         }
-        Fields::Unnamed(unnamed) => {
-            let idents = get_unnamed_idents(unnamed);
+        DerivedTypeEnum::PartialOrd => pord_methods(trait_to_impl)
+            .into_iter()
+            .map(PordMethod::signature)
+            .collect(),
+        DerivedTypeEnum::Hash => {
+            vec![quote! { #[inline] fn hash<H: std::hash::Hasher>(&self, _: &mut H) }]
+        }
+        DerivedTypeEnum::Debug => {
+            vec![quote! { fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result }]
+        }
+        DerivedTypeEnum::Copy | DerivedTypeEnum::Eq | DerivedTypeEnum::Default => vec![],
+    };
 
-            quote! {
-                fn clone(&self) -> Self {
-                    let Self( #(#idents),* ) = self;
-                    Self( #(#idents.clone()),* )
-                }
+    quote! {
+        #(
+            #signatures {
+                match *self {}
             }
+        )*
+    }
+}
+
+fn clone_from_copy() -> TokenStream {
+    quote! {
+        #[inline]
+        fn clone(&self) -> Self {
+            *self
         }
-        Fields::Unit => quote! {
+    }
+}
+
+/// Builds the struct or variant at `root` from an expression for each of its fields.
+fn construct(fields: &Fields, root: TokenStream, values: Vec<TokenStream>) -> TokenStream {
+    match fields {
+        Fields::Named(names) => {
+            let idents = get_named_idents(names);
+            quote! { #root{ #(#idents : #values),* } }
+        }
+        Fields::Unnamed(_) => quote! { #root( #(#values),* ) },
+        Fields::Unit => root,
+    }
+}
+
+fn clone_field(field: &Field, value: &Ident) -> TokenStream {
+    match FieldOptions::of(field).with_fn(DerivedTypeEnum::Clone) {
+        Some(with) => quote! { #with(#value) },
+        None => quote! { #value.clone() },
+    }
+}
+
+fn clone_from_field(field: &Field, dest: &Ident, source: &Ident) -> TokenStream {
+    match FieldOptions::of(field).with_fn(DerivedTypeEnum::Clone) {
+        Some(with) => quote! { *#dest = #with(#source); },
+        None => quote! { #dest.clone_from(#source); },
+    }
+}
+
+/// Clones each of the fields bound by the source pattern into those bound by the destination pattern.
+fn clone_from_fields(dest: &[(&Field, Ident)], source: &[(&Field, Ident)]) -> Vec<TokenStream> {
+    dest.iter()
+        .zip(source)
+        .map(|((field, dest), (_, source))| clone_from_field(field, dest, source))
+        .collect()
+}
+
+fn clone_struct(s: &ItemStruct) -> TokenStream {
+    if let Fields::Unit = s.fields {
+        return quote! {
+            #[inline]
             fn clone(&self) -> Self {
                 Self
             }
-        },
+        };
+    }
+
+    let (pattern, bound) = bind_fields(&s.fields, "v", |_| true);
+    let values = bound
+        .iter()
+        .map(|(field, ident)| clone_field(field, ident))
+        .collect();
+    let cloned = construct(&s.fields, quote! { Self }, values);
+
+    let (dest_pattern, dest) = bind_fields(&s.fields, "u", |_| true);
+    let clone_froms = clone_from_fields(&dest, &bound);
+
+    quote! {
+        #[inline]
+        fn clone(&self) -> Self {
+            let Self #pattern = self;
+            #cloned
+        }
+
+        #[inline]
+        fn clone_from(&mut self, source: &Self) {
+            let Self #dest_pattern = self;
+            let Self #pattern = source;
+            #(
+                #clone_froms
+            )*
+        }
     }
 }
 
@@ -335,28 +1316,33 @@ fn clone_enum(e: &ItemEnum) -> TokenStream {
         .iter()
         .map(|v| {
             let ident = v.ident.clone();
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
+            let (pattern, bound) = bind_variant(v, "v");
+            let values = bound
+                .iter()
+                .map(|(field, ident)| clone_field(field, ident))
+                .collect();
+            let cloned = construct(&v.fields, quote! { Self::#ident }, values);
 
-                    quote! {
-                        Self::#ident{#(#idents),*} => Self::#ident{#(#idents : #idents.clone()),*}
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents = get_unnamed_idents(unnamed);
+            quote! {
+                #pattern => #cloned
+            }
+        })
+        .collect::<Vec<_>>();
+    let variant_clone_from_cases = e
+        .variants
+        .iter()
+        .map(|v| {
+            let (dest_pattern, dest) = bind_variant(v, "u");
+            let (pattern, bound) = bind_variant(v, "v");
+            let clone_froms = clone_from_fields(&dest, &bound);
 
-                    quote! {
-                        Self::#ident(#(#idents),*) => Self::#ident(#(#idents.clone()),*)
-                    }
-                }
-                Fields::Unit => quote! {
-                    Self::#ident => Self::#ident
-                },
+            quote! {
+                (#dest_pattern, #pattern) => { #( #clone_froms )* }
             }
         })
         .collect::<Vec<_>>();
     quote! {
+        #[inline]
         fn clone(&self) -> Self {
             match self {
                 #(
@@ -364,73 +1350,410 @@ fn clone_enum(e: &ItemEnum) -> TokenStream {
                 ),*
             }
         }
+
+        #[inline]
+        fn clone_from(&mut self, source: &Self) {
+            match (self, source) {
+                #(
+                    #variant_clone_from_cases,
+                )*
+                (this, source) => *this = source.clone(),
+            }
+        }
     }
 }
 
-fn peq_struct(s: &ItemStruct) -> TokenStream {
-    match &s.fields {
+/// A field of `self` alongside the same field of `other`, each given as an expression evaluating to a
+/// reference to that field.
+struct FieldPair<'a> {
+    field: &'a Field,
+    lhs: TokenStream,
+    rhs: TokenStream,
+}
+
+/// Pairs up the fields of `self` and `other` by accessing them directly.
+fn field_pairs_by_member(fields: &Fields) -> Vec<FieldPair<'_>> {
+    fields
+        .iter()
+        .zip(fields.members())
+        .map(|(field, member)| FieldPair {
+            field,
+            lhs: quote! { (&self.#member) },
+            rhs: quote! { (&other.#member) },
+        })
+        .collect()
+}
+
+/// Pairs up the fields of `self` and `other` for a struct, putting the fields named by `key` first, in
+/// the order they are named.
+fn struct_field_pairs<'a>(s: &'a ItemStruct, traits: &DerivedList) -> Vec<FieldPair<'a>> {
+    let pairs = field_pairs_by_member(&s.fields);
+    let Some((_, key)) = &traits.1.key else {
+        return pairs;
+    };
+    let mut pairs = pairs
+        .into_iter()
+        .zip(s.fields.members())
+        .map(|(pair, member)| (key.iter().position(|k| *k == member), pair))
+        .collect::<Vec<_>>();
+    pairs.sort_by_key(|(position, _)| position.unwrap_or(usize::MAX));
+    pairs.into_iter().map(|(_, pair)| pair).collect()
+}
+
+/// Binds the fields kept by `keep` to identifiers ending with the given suffix, ignoring the rest. Gives
+/// the pattern doing the binding, to follow the path of the struct or variant, alongside each kept
+/// field and the identifier it was bound to.
+fn bind_fields<'a>(
+    fields: &'a Fields,
+    suffix: &str,
+    keep: impl Fn(&Field) -> bool,
+) -> (TokenStream, Vec<(&'a Field, Ident)>) {
+    let idents = match fields {
+        Fields::Named(names) => get_named_idents_suffix(names, suffix),
+        Fields::Unnamed(unnamed) => get_unnamed_idents_prefix(unnamed, suffix),
+        Fields::Unit => return (quote! {}, Vec::new()),
+    };
+    let (patterns, bound): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .zip(idents)
+        .map(|(field, ident)| {
+            if keep(field) {
+                (quote! { #ident }, Some((field, ident)))
+            } else {
+                (quote! { _ }, None)
+            }
+        })
+        .unzip();
+    let bound = bound.into_iter().flatten().collect();
+
+    let pattern = match fields {
         Fields::Named(names) => {
-            let idents = get_named_idents(names);
+            let names = get_named_idents(names);
+            quote! { {#(#names: #patterns),*} }
+        }
+        _ => quote! { (#(#patterns),*) },
+    };
+    (pattern, bound)
+}
 
-            quote! {
-                fn eq(&self, other: &Self) -> bool {
-                    true #(&& self.#idents.eq(& other.#idents))*
+/// Binds all of the fields of a variant to identifiers ending with the given suffix, giving the
+/// pattern doing the binding alongside each field and the identifier it was bound to.
+fn bind_variant<'a>(v: &'a Variant, suffix: &str) -> (TokenStream, Vec<(&'a Field, Ident)>) {
+    let ident = v.ident.clone();
+    let (pattern, bound) = bind_fields(&v.fields, suffix, |_| true);
+    (quote! { Self::#ident #pattern }, bound)
+}
+
+/// Binds the fields of a variant in both `self` and `other`, giving the patterns for each alongside
+/// the pairs of bound fields.
+fn bind_variant_pair(v: &Variant) -> (TokenStream, TokenStream, Vec<FieldPair<'_>>) {
+    let (lhs_pattern, lhs) = bind_variant(v, "u");
+    let (rhs_pattern, rhs) = bind_variant(v, "v");
+    let pairs = lhs
+        .into_iter()
+        .zip(rhs)
+        .map(|((field, lhs), (_, rhs))| FieldPair {
+            field,
+            lhs: quote! { #lhs },
+            rhs: quote! { #rhs },
+        })
+        .collect();
+    (lhs_pattern, rhs_pattern, pairs)
+}
+
+/// Whether a field takes part in the implementation of the given trait.
+fn is_field_compared(field: &Field, name: DerivedTypeEnum) -> bool {
+    !FieldOptions::of(field).skips(name)
+}
+
+/// Whether a field must implement the given trait, as it is neither skipped nor given a function to
+/// use in place of the trait.
+fn is_field_bounded(field: &Field, name: DerivedTypeEnum) -> bool {
+    !FieldOptions::of(field).replaces(name)
+}
+
+fn compared_fields(pairs: Vec<FieldPair<'_>>, name: DerivedTypeEnum) -> Vec<FieldPair<'_>> {
+    pairs
+        .into_iter()
+        .filter(|pair| is_field_compared(pair.field, name))
+        .collect()
+}
+
+/// The fields taking part in the implementation of `Ord` or `PartialOrd`, with the fields given by
+/// `order(...)` replaced by their keys and swapped where reversed.
+fn ordered_fields(pairs: Vec<FieldPair<'_>>, name: DerivedTypeEnum) -> Vec<FieldPair<'_>> {
+    compared_fields(pairs, name)
+        .into_iter()
+        .map(|FieldPair { field, lhs, rhs }| {
+            let Some(order) = FieldOptions::of(field).order else {
+                return FieldPair { field, lhs, rhs };
+            };
+            let (lhs, rhs) = match &order.by {
+                Some(by) => (quote! { (&#by(#lhs)) }, quote! { (&#by(#rhs)) }),
+                None => (lhs, rhs),
+            };
+            let (lhs, rhs) = match order.reverse {
+                Some(_) => (rhs, lhs),
+                None => (lhs, rhs),
+            };
+            FieldPair { field, lhs, rhs }
+        })
+        .collect()
+}
+
+/// Compares a pair of fields with the function given for the trait, or with the given method of the
+/// trait otherwise.
+fn compare_field(pair: &FieldPair, name: DerivedTypeEnum, method: TokenStream) -> TokenStream {
+    let FieldPair { field, lhs, rhs } = pair;
+    let options = FieldOptions::of(field);
+    if options.compares_by_ptr(name) {
+        let (lhs, rhs) = (pointer_address(lhs), pointer_address(rhs));
+        return quote! { #lhs.#method(&#rhs) };
+    }
+    if options.compares_total(name) {
+        let cmp = total_cmp(&field.ty, lhs, rhs, 0);
+        return match name {
+            DerivedTypeEnum::PartialEq => quote! { #cmp.is_eq() },
+            DerivedTypeEnum::PartialOrd => quote! { Some(#cmp) },
+            _ => cmp,
+        };
+    }
+    match options.with_fn(name) {
+        Some(with) => quote! { #with(#lhs, #rhs) },
+        None => quote! { #lhs.#method(#rhs) },
+    }
+}
+
+/// Whether a type only holds floats, which can be compared by their total order.
+fn is_float_type(ty: &Type) -> bool {
+    match ty {
+        Type::Paren(ty) => is_float_type(&ty.elem),
+        Type::Array(array) => is_float_type(&array.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                PathArguments::None => {
+                    path.path.segments.len() == 1 && (last.ident == "f32" || last.ident == "f64")
+                }
+                PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+                    match args.args.first() {
+                        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => {
+                            is_float_type(inner)
+                        }
+                        _ => false,
+                    }
                 }
+                _ => false,
             }
         }
-        Fields::Unnamed(unnamed) => {
-            let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-            let idents2 = get_unnamed_idents_prefix(unnamed, "v");
+        _ => false,
+    }
+}
 
-            quote! {
-                fn eq(&self, other: &Self) -> bool {
-                    let Self( #(#idents1),* ) = self;
-                    let Self( #(#idents2),* ) = other;
-                    true #(&& #idents1.eq(#idents2))*
-                }
+/// The type held by an `Option` or array, as accepted by `is_float_type`.
+fn float_inner_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Paren(ty) => float_inner_type(&ty.elem),
+        Type::Array(array) => Some(&array.elem),
+        Type::Path(path) => match &path.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(args) => match args.args.first()? {
+                GenericArgument::Type(inner) => Some(inner),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_array_type(ty: &Type) -> bool {
+    match ty {
+        Type::Paren(ty) => is_array_type(&ty.elem),
+        Type::Array(_) => true,
+        _ => false,
+    }
+}
+
+/// Names for the values bound while comparing the contents of an `Option` or array, unique to
+/// each level of nesting.
+fn total_bindings(depth: usize) -> (Ident, Ident) {
+    (
+        Ident::new(&format!("lhs{}", depth), Span::call_site()),
+        Ident::new(&format!("rhs{}", depth), Span::call_site()),
+    )
+}
+
+/// Compares two references to a float type by its total order, giving an `Ordering`.
+fn total_cmp(ty: &Type, lhs: &TokenStream, rhs: &TokenStream, depth: usize) -> TokenStream {
+    let Some(inner) = float_inner_type(ty) else {
+        return quote! { #lhs.total_cmp(#rhs) };
+    };
+    let (l, r) = total_bindings(depth);
+    let inner = total_cmp(inner, &quote! { #l }, &quote! { #r }, depth + 1);
+    if is_array_type(ty) {
+        quote! {
+            #lhs.iter()
+                .zip(#rhs.iter())
+                .map(|(#l, #r)| #inner)
+                .find(|cmp| cmp.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }
+    } else {
+        quote! {
+            match (#lhs, #rhs) {
+                (Some(#l), Some(#r)) => #inner,
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (Some(_), None) => std::cmp::Ordering::Greater,
             }
         }
-        Fields::Unit => quote! {
-            fn eq(&self, other: &Self) -> bool {
-                true
+    }
+}
+
+/// Hashes a reference to a float type consistently with its total order.
+fn total_hash(ty: &Type, value: &TokenStream, depth: usize) -> TokenStream {
+    let Some(inner) = float_inner_type(ty) else {
+        return quote! { #value.to_bits().hash(state); };
+    };
+    let (v, _) = total_bindings(depth);
+    let inner = total_hash(inner, &quote! { #v }, depth + 1);
+    if is_array_type(ty) {
+        quote! {
+            for #v in #value.iter() {
+                #inner
             }
-        },
+        }
+    } else {
+        quote! {
+            std::mem::discriminant(#value).hash(state);
+            if let Some(#v) = #value {
+                #inner
+            }
+        }
+    }
+}
+
+/// The address pointed to by a reference to a pointer, without any metadata, so that pointers to the
+/// same value always compare equal, as with `Rc::ptr_eq`.
+fn pointer_address(pointer: &TokenStream) -> TokenStream {
+    quote! { std::ptr::from_ref(&**#pointer).cast::<()>() }
+}
+
+fn peq_field(pair: &FieldPair) -> TokenStream {
+    compare_field(pair, DerivedTypeEnum::PartialEq, quote! { eq })
+}
+
+fn ord_field(pair: &FieldPair) -> TokenStream {
+    compare_field(pair, DerivedTypeEnum::Ord, quote! { cmp })
+}
+
+fn pord_field(pair: &FieldPair) -> TokenStream {
+    compare_field(pair, DerivedTypeEnum::PartialOrd, quote! { partial_cmp })
+}
+
+/// With `cheap_first`, orders the fields compared by `PartialEq` by their priority, so that those
+/// cheapest to compare are compared first. As `&&` stops at the first field found to differ, this
+/// only changes how soon a difference is found, not the result.
+fn eq_ordered_fields<'a>(
+    pairs: Vec<FieldPair<'a>>,
+    trait_to_impl: &DerivedType,
+) -> Vec<FieldPair<'a>> {
+    let mut pairs = compared_fields(pairs, DerivedTypeEnum::PartialEq);
+    if trait_to_impl.has_option("cheap_first") {
+        pairs.sort_by_key(|pair| std::cmp::Reverse(eq_priority(pair.field)));
+    }
+    pairs
+}
+
+/// The priority given to a field by `priority = N`, or else 1 for fields cheap to compare and 0
+/// for the rest.
+fn eq_priority(field: &Field) -> i32 {
+    let options = FieldOptions::of(field);
+    if let Some((_, priority)) = options.priority {
+        return priority;
+    }
+    let cheap = options.by_ptr.is_some()
+        || (options.with_fn(DerivedTypeEnum::PartialEq).is_none() && is_cheap_type(&field.ty));
+    i32::from(cheap)
+}
+
+/// Whether a type is cheap to compare, judged by its syntax alone: primitives, and pointers, references,
+/// tuples and `Option`s of them.
+fn is_cheap_type(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+        "i128", "isize", "f32", "f64",
+    ];
+    match ty {
+        Type::Paren(ty) => is_cheap_type(&ty.elem),
+        Type::Group(ty) => is_cheap_type(&ty.elem),
+        Type::Reference(ty) => is_cheap_type(&ty.elem),
+        Type::Tuple(ty) => ty.elems.iter().all(is_cheap_type),
+        Type::Ptr(_) | Type::Never(_) => true,
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                PathArguments::None => {
+                    path.path.segments.len() == 1
+                        && PRIMITIVES.iter().any(|primitive| last.ident == primitive)
+                }
+                PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+                    match args.args.first() {
+                        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => {
+                            is_cheap_type(inner)
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn peq_struct(s: &ItemStruct, trait_to_impl: &DerivedType, traits: &DerivedList) -> TokenStream {
+    let pairs = eq_ordered_fields(struct_field_pairs(s, traits), trait_to_impl);
+    let comparisons = pairs.iter().map(peq_field);
+
+    quote! {
+        #[inline]
+        fn eq(&self, other: &Self) -> bool {
+            true #(&& #comparisons)*
+        }
+    }
+}
+
+/// The variants of a fieldless enum are equal exactly when their discriminants are.
+fn peq_fieldless_enum() -> TokenStream {
+    quote! {
+        #[inline]
+        fn eq(&self, other: &Self) -> bool {
+            std::mem::discriminant(self) == std::mem::discriminant(other)
+        }
     }
 }
 
-fn peq_enum(e: &ItemEnum) -> TokenStream {
+fn peq_enum(e: &ItemEnum, trait_to_impl: &DerivedType) -> TokenStream {
     let variant_cases = e
         .variants
         .iter()
         .map(|v| {
-            let ident = v.ident.clone();
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
-                    let idents1 = get_named_idents_suffix(names, "u");
-                    let idents2 = get_named_idents_suffix(names, "v");
+            let (lhs_pattern, rhs_pattern, pairs) = bind_variant_pair(v);
+            let pairs = eq_ordered_fields(pairs, trait_to_impl);
+            let comparisons = pairs.iter().map(peq_field);
 
-                    quote! {
-                        (Self::#ident{#(#idents: #idents1),*}, Self::#ident{#(#idents: #idents2),*})
-                            => true #(&& #idents1.eq(#idents2))*
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-                    let idents2 = get_unnamed_idents_prefix(unnamed, "v");
-
-                    quote! {
-                        (Self::#ident(#(#idents1),*), Self::#ident(#(#idents2),*))
-                            => true #(&& #idents1.eq(#idents2))*
-                    }
-                }
-                Fields::Unit => quote! {
-                    (Self::#ident, Self::#ident) => true
-                },
+            quote! {
+                (#lhs_pattern, #rhs_pattern) => true #(&& #comparisons)*
             }
         })
         .collect::<Vec<_>>();
     quote! {
+        #[inline]
         fn eq(&self, other: &Self) -> bool {
             match (self, other) {
                 #(
@@ -442,43 +1765,141 @@ fn peq_enum(e: &ItemEnum) -> TokenStream {
     }
 }
 
-fn ord_struct(s: &ItemStruct) -> TokenStream {
-    match &s.fields {
-        Fields::Named(names) => {
-            let idents = get_named_idents(names);
-
-            quote! {
-                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                    std::cmp::Ordering::Equal #(.then(self.#idents.cmp(&other.#idents)))*
+/// Evaluates each comparison in turn, returning as soon as one isn't `equal`.
+fn comparison_chain(comparisons: &[TokenStream], equal: TokenStream) -> TokenStream {
+    let Some((last, comparisons)) = comparisons.split_last() else {
+        return equal;
+    };
+    quote! {
+        {
+            #(
+                match #comparisons {
+                    #equal => {}
+                    cmp => return cmp,
                 }
-            }
+            )*
+            #last
         }
-        Fields::Unnamed(unnamed) => {
-            let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-            let idents2 = get_unnamed_idents_prefix(unnamed, "v");
+    }
+}
 
-            quote! {
-                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                    let Self( #(#idents1),* ) = self;
-                    let Self( #(#idents2),* ) = other;
-                    std::cmp::Ordering::Equal #(.then(#idents1.cmp(#idents2)))*
-                }
-            }
+fn ord_chain(pairs: &[FieldPair]) -> TokenStream {
+    let comparisons = pairs.iter().map(ord_field).collect::<Vec<_>>();
+    comparison_chain(&comparisons, quote! { std::cmp::Ordering::Equal })
+}
+
+fn pord_chain(pairs: &[FieldPair]) -> TokenStream {
+    let comparisons = pairs.iter().map(pord_field).collect::<Vec<_>>();
+    comparison_chain(&comparisons, quote! { Some(std::cmp::Ordering::Equal) })
+}
+
+fn ord_struct(s: &ItemStruct, traits: &DerivedList) -> TokenStream {
+    let pairs = ordered_fields(struct_field_pairs(s, traits), DerivedTypeEnum::Ord);
+    let chain = ord_chain(&pairs);
+
+    quote! {
+        #[inline]
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            #chain
         }
-        Fields::Unit => quote! {
-            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                std::cmp::Ordering::Equal
+    }
+}
+
+const REPR_INTEGER_TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+fn enum_repr_type(e: &ItemEnum) -> Option<Ident> {
+    e.attrs
+        .iter()
+        .filter(|a| a.path().is_ident("repr"))
+        .filter_map(|a| {
+            a.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .filter_map(|m| m.path().get_ident().cloned())
+        .find(|i| REPR_INTEGER_TYPES.iter().any(|ty| i == ty))
+}
+
+/// Gives an expression for the value of each variant, given the explicit values of some of them.
+/// Variants without an explicit value take the value of the previous variant plus one, starting
+/// from zero, as discriminants do.
+fn enum_values<'a>(explicit: impl Iterator<Item = Option<&'a Expr>>) -> Vec<TokenStream> {
+    let mut base = None;
+    let mut offset = 0;
+    explicit
+        .map(|expr| {
+            if let Some(expr) = expr {
+                base = Some(expr);
+                offset = 0;
             }
-        },
+            let offset_lit = Literal::usize_unsuffixed(offset);
+            offset += 1;
+
+            match base {
+                Some(expr) if offset == 1 => quote! { #expr },
+                Some(expr) => quote! { (#expr) + #offset_lit },
+                None => quote! { #offset_lit },
+            }
+        })
+        .collect()
+}
+
+/// Gives the type and an expression for the value each variant is ordered by. This is the
+/// discriminant, unless any variant is given an `order`, in which case variants are ordered by
+/// those, numbered in the same way as discriminants.
+fn enum_ranks(e: &ItemEnum) -> (Ident, Vec<TokenStream>) {
+    let options = e
+        .variants
+        .iter()
+        .map(VariantOptions::of)
+        .collect::<Vec<_>>();
+    if options.iter().any(|o| o.order.is_some()) {
+        let orders = options
+            .iter()
+            .map(|o| o.order.as_ref().map(|(_, expr)| expr));
+        return (Ident::new("isize", e.span()), enum_values(orders));
     }
+
+    let discriminant_ty = enum_repr_type(e).unwrap_or_else(|| Ident::new("isize", e.span()));
+    let discriminants = e
+        .variants
+        .iter()
+        .map(|v| v.discriminant.as_ref().map(|(_, expr)| expr));
+    (discriminant_ty, enum_values(discriminants))
 }
 
-fn enum_cmp_lexographic(e: &ItemEnum) -> TokenStream {
+/// Whether an enum has no fields in any of its variants, so that its variants can be told apart by
+/// their discriminants alone.
+fn is_fieldless(e: &ItemEnum) -> bool {
+    e.variants.iter().all(|v| v.fields.is_empty())
+}
+
+/// Whether the variants of a fieldless enum can be cast to their discriminants, which requires the
+/// enum to be `Copy`.
+fn is_castable(e: &ItemEnum, traits: &DerivedList) -> bool {
+    is_fieldless(e) && traits.contains(DerivedTypeEnum::Copy)
+}
+
+/// Compares the variants of `self` and `other` by their ranks, as given by `enum_ranks`.
+fn enum_cmp_lexographic(e: &ItemEnum, traits: &DerivedList) -> TokenStream {
+    let (discriminant_ty, ranks) = enum_ranks(e);
+    let ordered = e
+        .variants
+        .iter()
+        .any(|v| VariantOptions::of(v).order.is_some());
+    if is_castable(e, traits) && !ordered {
+        return quote! {
+            (*self as #discriminant_ty).cmp(&(*other as #discriminant_ty))
+        };
+    }
+
     let variant_cases = e
         .variants
         .iter()
-        .enumerate()
-        .map(|(i, v)| {
+        .zip(ranks)
+        .map(|(v, discriminant)| {
             let ident = v.ident.clone();
             let match_vars = match &v.fields {
                 Fields::Named(_) => {
@@ -498,58 +1919,47 @@ fn enum_cmp_lexographic(e: &ItemEnum) -> TokenStream {
             };
 
             quote! {
-                Self::#ident #match_vars => #i
+                Self::#ident #match_vars => #discriminant
             }
         })
         .collect::<Vec<_>>();
     quote! {
         {
-            let i1 = match self {
-                #(#variant_cases),*
-            };
-            let i2 = match other {
-                #(#variant_cases),*
+            let rank = |value: &Self| -> #discriminant_ty {
+                match value {
+                    #(#variant_cases),*
+                }
             };
-            i1.cmp(&i2)
+            rank(self).cmp(&rank(other))
         }
     }
 }
 
-fn ord_enum(e: &ItemEnum) -> TokenStream {
+fn ord_enum(e: &ItemEnum, traits: &DerivedList) -> TokenStream {
+    let base_case = enum_cmp_lexographic(e, traits);
+    if is_fieldless(e) {
+        return quote! {
+            #[inline]
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                #base_case
+            }
+        };
+    }
+
     let variant_cases = e
         .variants
         .iter()
         .map(|v| {
-            let ident = v.ident.clone();
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
-                    let idents1 = get_named_idents_suffix(names, "u");
-                    let idents2 = get_named_idents_suffix(names, "v");
-
-                    quote! {
-                        (Self::#ident{#(#idents: #idents1),*}, Self::#ident{#(#idents: #idents2),*})
-                            => std::cmp::Ordering::Equal #(.then(#idents1.cmp(#idents2)))*
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-                    let idents2 = get_unnamed_idents_prefix(unnamed, "v");
+            let (lhs_pattern, rhs_pattern, pairs) = bind_variant_pair(v);
+            let chain = ord_chain(&ordered_fields(pairs, DerivedTypeEnum::Ord));
 
-                    quote! {
-                        (Self::#ident(#(#idents1),*), Self::#ident(#(#idents2),*))
-                            => std::cmp::Ordering::Equal #(.then(#idents1.cmp(#idents2)))*
-                    }
-                }
-                Fields::Unit => quote! {
-                    (Self::#ident, Self::#ident) => std::cmp::Ordering::Equal
-                },
+            quote! {
+                (#lhs_pattern, #rhs_pattern) => #chain
             }
         })
         .collect::<Vec<_>>();
-
-    let base_case = enum_cmp_lexographic(e);
     quote! {
+        #[inline]
         fn cmp(&self, other: &Self) -> std::cmp::Ordering {
             match (self, other) {
                 #(
@@ -561,138 +1971,202 @@ fn ord_enum(e: &ItemEnum) -> TokenStream {
     }
 }
 
-fn build_nested_tuple(ids: &[impl ToTokens]) -> TokenStream {
-    let Some(id1) = ids.first() else {
-        return quote! { () };
-    };
-    let rest = build_nested_tuple(&ids[1..]);
-    quote! {
-        (#id1, #rest)
-    }
+/// The methods of `PartialOrd`, each of which can be evaluated lexicographically over fields.
+#[derive(Clone, Copy)]
+enum PordMethod {
+    PartialCmp,
+    Lt,
+    Le,
+    Gt,
+    Ge,
 }
 
-fn pord_struct(s: &ItemStruct) -> TokenStream {
-    match &s.fields {
-        Fields::Named(names) => {
-            let idents = get_named_idents(names);
-            let idents1: Vec<TokenStream> =
-                idents.iter().map(|ident| quote! {&self.#ident}).collect();
-            let idents2: Vec<TokenStream> =
-                idents.iter().map(|ident| quote! {&other.#ident}).collect();
-
-            let t1 = build_nested_tuple(&idents1);
-            let t2 = build_nested_tuple(&idents2);
+impl PordMethod {
+    const OPERATORS: [PordMethod; 4] = [Self::Lt, Self::Le, Self::Gt, Self::Ge];
 
-            quote! {
-                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                    #t1.partial_cmp(&#t2)
+    fn signature(self) -> TokenStream {
+        let name = match self {
+            PordMethod::PartialCmp => {
+                return quote! {
+                    #[inline]
+                    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering>
                 }
             }
+            PordMethod::Lt => quote! { lt },
+            PordMethod::Le => quote! { le },
+            PordMethod::Gt => quote! { gt },
+            PordMethod::Ge => quote! { ge },
+        };
+        quote! {
+            #[inline]
+            fn #name(&self, other: &Self) -> bool
         }
-        Fields::Unnamed(unnamed) => {
-            let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-            let idents2 = get_unnamed_idents_prefix(unnamed, "v");
-
-            let t1 = build_nested_tuple(&idents1);
-            let t2 = build_nested_tuple(&idents2);
+    }
 
-            quote! {
-                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                    let Self( #(#idents1),* ) = self;
-                    let Self( #(#idents2),* ) = other;
+    /// Converts the result of a total comparison into the result of this method.
+    fn result_of_ordering(self, ordering: TokenStream) -> TokenStream {
+        match self {
+            PordMethod::PartialCmp => quote! { Some(#ordering) },
+            PordMethod::Lt => quote! { std::cmp::Ordering::is_lt(#ordering) },
+            PordMethod::Le => quote! { std::cmp::Ordering::is_le(#ordering) },
+            PordMethod::Gt => quote! { std::cmp::Ordering::is_gt(#ordering) },
+            PordMethod::Ge => quote! { std::cmp::Ordering::is_ge(#ordering) },
+        }
+    }
 
-                    #t1.partial_cmp(&#t2)
-                }
+    /// Compares each pair of values in turn, deciding the result at the first pair that isn't equal.
+    /// The operators defer to the operator itself for the final pair, as the std tuple impls do.
+    fn chain(self, pairs: &[FieldPair]) -> TokenStream {
+        let (name, is_name, decisive) = match self {
+            PordMethod::PartialCmp => return pord_chain(pairs),
+            PordMethod::Lt => (quote! { lt }, quote! { is_lt }, quote! { Less }),
+            PordMethod::Le => (quote! { le }, quote! { is_le }, quote! { Less }),
+            PordMethod::Gt => (quote! { gt }, quote! { is_gt }, quote! { Greater }),
+            PordMethod::Ge => (quote! { ge }, quote! { is_ge }, quote! { Greater }),
+        };
+        let Some((last, pairs)) = pairs.split_last() else {
+            return self.result_of_ordering(quote! { std::cmp::Ordering::Equal });
+        };
+        let comparisons = pairs.iter().map(pord_field);
+        let last = if is_field_bounded(last.field, DerivedTypeEnum::PartialOrd) {
+            let FieldPair { lhs, rhs, .. } = last;
+            quote! { #lhs.#name(#rhs) }
+        } else {
+            let cmp = pord_field(last);
+            quote! { #cmp.is_some_and(std::cmp::Ordering::#is_name) }
+        };
+        quote! {
+            {
+                #(
+                    match #comparisons {
+                        Some(std::cmp::Ordering::Equal) => {}
+                        cmp => return cmp == Some(std::cmp::Ordering::#decisive),
+                    }
+                )*
+                #last
             }
         }
-        Fields::Unit => quote! {
-            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                Some(std::cmp::Ordering::Equal)
-            }
-        },
     }
 }
 
-fn pord_enum(e: &ItemEnum) -> TokenStream {
+fn pord_methods(trait_to_impl: &DerivedType) -> Vec<PordMethod> {
+    let mut methods = vec![PordMethod::PartialCmp];
+    if trait_to_impl.has_option("operators") {
+        methods.extend(PordMethod::OPERATORS);
+    }
+    methods
+}
+
+fn pord(trait_to_impl: &DerivedType, traits: &DerivedList, obj: &StructOrEnum) -> TokenStream {
+    pord_methods(trait_to_impl)
+        .into_iter()
+        .map(|method| match obj {
+            _ if traits.contains(DerivedTypeEnum::Ord) => pord_from_ord(method),
+            StructOrEnum::Struct(s) => pord_struct(s, traits, method),
+            StructOrEnum::Enum(e) => pord_enum(e, traits, method),
+            StructOrEnum::Union(_) => unreachable!("PartialOrd can't be derived for unions"),
+        })
+        .collect()
+}
+
+fn pord_from_ord(method: PordMethod) -> TokenStream {
+    let signature = method.signature();
+    let result = method.result_of_ordering(quote! { std::cmp::Ord::cmp(self, other) });
+    quote! {
+        #signature {
+            #result
+        }
+    }
+}
+
+fn pord_struct(s: &ItemStruct, traits: &DerivedList, method: PordMethod) -> TokenStream {
+    let signature = method.signature();
+    let pairs = ordered_fields(struct_field_pairs(s, traits), DerivedTypeEnum::PartialOrd);
+    let chain = method.chain(&pairs);
+
+    quote! {
+        #signature {
+            #chain
+        }
+    }
+}
+
+fn pord_enum(e: &ItemEnum, traits: &DerivedList, method: PordMethod) -> TokenStream {
+    let signature = method.signature();
+    let base_case = method.result_of_ordering(enum_cmp_lexographic(e, traits));
+    if is_fieldless(e) {
+        return quote! {
+            #signature {
+                #base_case
+            }
+        };
+    }
+
     let variant_cases = e
         .variants
         .iter()
         .map(|v| {
-            let ident = v.ident.clone();
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
-                    let idents1 = get_named_idents_suffix(names, "u");
-                    let idents2 = get_named_idents_suffix(names, "v");
-
-                    let t1 = build_nested_tuple(&idents1);
-                    let t2 = build_nested_tuple(&idents2);
-
-                    quote! {
-                        (Self::#ident{#(#idents: #idents1),*}, Self::#ident{#(#idents: #idents2),*})
-                            => #t1.partial_cmp(&#t2)
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents1 = get_unnamed_idents_prefix(unnamed, "u");
-                    let idents2 = get_unnamed_idents_prefix(unnamed, "v");
-
-                    let t1 = build_nested_tuple(&idents1);
-                    let t2 = build_nested_tuple(&idents2);
+            let (lhs_pattern, rhs_pattern, pairs) = bind_variant_pair(v);
+            let chain = method.chain(&ordered_fields(pairs, DerivedTypeEnum::PartialOrd));
 
-                    quote! {
-                        (Self::#ident(#(#idents1),*), Self::#ident(#(#idents2),*))
-                            => #t1.partial_cmp(&#t2)
-                    }
-                }
-                Fields::Unit => quote! {
-                    (Self::#ident, Self::#ident) => Some(std::cmp::Ordering::Equal)
-                },
+            quote! {
+                (#lhs_pattern, #rhs_pattern) => #chain
             }
         })
         .collect::<Vec<_>>();
-
-    let base_case = enum_cmp_lexographic(e);
     quote! {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        #signature {
             match (self, other) {
                 #(
                     #variant_cases,
                 )*
-                _ => Some(#base_case)
+                _ => #base_case
             }
         }
     }
 }
 
-fn hash_struct(s: &ItemStruct) -> TokenStream {
-    match &s.fields {
-        Fields::Named(names) => {
-            let idents = get_named_idents(names);
+fn hash_field(field: &Field, value: TokenStream) -> Option<TokenStream> {
+    if !is_field_compared(field, DerivedTypeEnum::Hash) {
+        return None;
+    }
+    let options = FieldOptions::of(field);
+    if options.compares_by_ptr(DerivedTypeEnum::Hash) {
+        let address = pointer_address(&value);
+        return Some(quote! { #address.hash(state); });
+    }
+    if options.compares_total(DerivedTypeEnum::Hash) {
+        let hash = total_hash(&field.ty, &value, 0);
+        return Some(quote! { { #hash } });
+    }
+    match options.with_fn(DerivedTypeEnum::Hash) {
+        Some(with) => Some(quote! { #with(#value, state); }),
+        None => Some(quote! { #value.hash(state); }),
+    }
+}
 
-            quote! {
-                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                    #(
-                        self.#idents.hash(state);
-                    )*
-                }
-            }
+fn hash_struct(s: &ItemStruct, traits: &DerivedList) -> TokenStream {
+    let hashes = struct_field_pairs(s, traits)
+        .into_iter()
+        .filter_map(|pair| hash_field(pair.field, pair.lhs));
+
+    quote! {
+        #[inline]
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            #(
+                #hashes
+            )*
         }
-        Fields::Unnamed(unnamed) => {
-            let idents = get_unnamed_idents(unnamed);
+    }
+}
 
-            quote! {
-                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                    let Self( #(#idents),* ) = self;
-                    #(
-                        #idents.hash(state);
-                    )*
-                }
-            }
+/// Hashes the discriminant alone, as `hash_enum` would for an enum without fields.
+fn hash_fieldless_enum() -> TokenStream {
+    quote! {
+        #[inline]
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            std::hash::Hash::hash(&std::mem::discriminant(self), state);
         }
-        Fields::Unit => quote! {
-            fn hash<H: std::hash::Hasher>(&self, _: &mut H) { }
-        },
     }
 }
 
@@ -701,36 +2175,21 @@ fn hash_enum(e: &ItemEnum) -> TokenStream {
         .variants
         .iter()
         .map(|v| {
-            let ident = v.ident.clone();
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
+            let (pattern, bound) = bind_variant(v, "v");
+            let hashes = bound
+                .into_iter()
+                .filter_map(|(field, ident)| hash_field(field, quote! { #ident }));
 
-                    quote! {
-                        Self::#ident{#(#idents),*}
-                            => {
-                                #( #idents.hash(state); )*
-                            }
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents = get_unnamed_idents(unnamed);
-
-                    quote! {
-                        Self::#ident(#(#idents),*)
-                            => {
-                                #( #idents.hash(state); )*
-                            }
-                    }
+            quote! {
+                #pattern => {
+                    #( #hashes )*
                 }
-                Fields::Unit => quote! {
-                    Self::#ident => {}
-                },
             }
         })
         .collect::<Vec<_>>();
 
     quote! {
+        #[inline]
         fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
             let dis = std::mem::discriminant(self);
             dis.hash(state);
@@ -743,86 +2202,251 @@ fn hash_enum(e: &ItemEnum) -> TokenStream {
     }
 }
 
-fn debug_struct(s: &ItemStruct) -> TokenStream {
-    let name = s.ident.clone();
-    match &s.fields {
-        Fields::Named(names) => {
-            let idents = get_named_idents(names);
+/// Hashes each variant's tag as a little-endian `u32`, followed by its fields in the order they
+/// are declared, so that the hash doesn't depend on how the compiler represents discriminants.
+/// The tag is the variant's index, unless any variant is given a `hash_tag`, in which case the
+/// tags are numbered in the same way as discriminants.
+fn hash_stable_enum(e: &ItemEnum) -> TokenStream {
+    let options = e
+        .variants
+        .iter()
+        .map(VariantOptions::of)
+        .collect::<Vec<_>>();
+    let tags = enum_values(
+        options
+            .iter()
+            .map(|o| o.hash_tag.as_ref().map(|(_, expr)| expr)),
+    );
+    let variant_cases = e
+        .variants
+        .iter()
+        .zip(tags)
+        .map(|(v, tag)| {
+            let (pattern, bound) = bind_variant(v, "v");
+            let hashes = bound
+                .into_iter()
+                .filter_map(|(field, ident)| hash_field(field, quote! { #ident }));
 
             quote! {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    f.debug_struct(stringify!(#name))
-                    #(
-                        .field(stringify!(#idents), &self.#idents)
-                    )*
-                        .finish()
+                #pattern => {
+                    let tag: u32 = #tag;
+                    state.write(&tag.to_le_bytes());
+                    #( #hashes )*
                 }
             }
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        #[inline]
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            match self {
+                #(
+                    #variant_cases,
+                )*
+            }
         }
-        Fields::Unnamed(unnamed) => {
-            let idents = get_unnamed_idents(unnamed);
+    }
+}
 
-            quote! {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    let Self( #(#idents),* ) = self;
+/// Whether a field is printed by `Debug` at all. With `non_exhaustive`, only public fields are printed.
+fn is_debug_printed(field: &Field, trait_to_impl: &DerivedType) -> bool {
+    !FieldOptions::of(field).skips(DerivedTypeEnum::Debug)
+        && (!trait_to_impl.has_option("non_exhaustive")
+            || matches!(field.vis, Visibility::Public(_)))
+}
 
-                    f.debug_tuple(stringify!(#name))
-                    #(
-                        .field(#idents)
-                    )*
-                        .finish()
-                }
+/// Whether a field is printed through its own `Debug` implementation, and so must be `Debug`.
+fn is_debug_bounded(field: &Field, trait_to_impl: &DerivedType) -> bool {
+    let options = FieldOptions::of(field).debug;
+    is_debug_printed(field, trait_to_impl) && options.redact.is_none() && options.with.is_none()
+}
+
+/// Whether any of the fields are formatted with a function given by `Debug(with = ...)`.
+fn uses_debug_with<'a>(mut fields: impl Iterator<Item = &'a Field>) -> bool {
+    fields.any(|field| FieldOptions::of(field).debug.with.is_some())
+}
+
+/// Adapts a function formatting a field into something implementing `Debug`.
+fn debug_with_adapter() -> TokenStream {
+    quote! {
+        struct DebugWith<'a, T: ?Sized>(
+            &'a T,
+            fn(&T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+        );
+
+        impl<T: ?Sized> std::fmt::Debug for DebugWith<'_, T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                (self.1)(self.0, f)
             }
         }
-        Fields::Unit => quote! {
+    }
+}
+
+/// The call adding a field, bound to the given identifier, to a `DebugStruct` or `DebugTuple`.
+fn debug_field(field: &Field, ident: &Ident) -> TokenStream {
+    let options = FieldOptions::of(field).debug;
+    let value = if options.redact.is_some() {
+        quote! { &"<redacted>" }
+    } else if let Some(with) = options.with {
+        quote! { &DebugWith(#ident, #with) }
+    } else {
+        quote! { #ident }
+    };
+
+    match (&field.ident, options.rename) {
+        (Some(_), Some(rename)) => quote! { .field(#rename, #value) },
+        (Some(name), None) => quote! { .field(stringify!(#name), #value) },
+        (None, _) => quote! { .field(#value) },
+    }
+}
+
+/// Formats the fields bound to the given identifiers, as a struct or tuple depending on the fields.
+fn debug_fields(
+    fields: &Fields,
+    name: TokenStream,
+    bound: &[(&Field, Ident)],
+    finish: TokenStream,
+) -> TokenStream {
+    let calls = bound.iter().map(|(field, ident)| debug_field(field, ident));
+    match fields {
+        Fields::Named(_) => quote! {
+            f.debug_struct(#name)
+            #(
+                #calls
+            )*
+                .#finish()
+        },
+        _ => quote! {
+            f.debug_tuple(#name)
+            #(
+                #calls
+            )*
+                .#finish()
+        },
+    }
+}
+
+fn debug_struct(s: &ItemStruct, trait_to_impl: &DerivedType) -> syn::Result<TokenStream> {
+    if let Some(transparent) = trait_to_impl.option("transparent") {
+        if trait_to_impl.options.len() > 1 {
+            return Err(syn::Error::new(
+                transparent.span(),
+                "Debug(transparent) cannot be combined with other options",
+            ));
+        }
+        if s.fields.len() != 1 {
+            return Err(syn::Error::new(
+                transparent.span(),
+                "Debug(transparent) requires a struct with exactly one field",
+            ));
+        }
+        let field = s.fields.iter().next().unwrap();
+        if FieldOptions::of(field).has_debug_options() {
+            return Err(syn::Error::new(
+                transparent.span(),
+                "Debug(transparent) cannot be combined with field options",
+            ));
+        }
+        let member = s.fields.members().next().unwrap();
+        return Ok(quote! {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.debug_tuple(stringify!(#name)).finish()
+                std::fmt::Debug::fmt(&self.#member, f)
             }
-        },
+        });
     }
+
+    let name = s.ident.clone();
+    let name = if trait_to_impl.has_option("with_generics") {
+        quote! { std::any::type_name::<Self>() }
+    } else {
+        quote! { stringify!(#name) }
+    };
+    let finish = if trait_to_impl.has_option("non_exhaustive") {
+        quote! { finish_non_exhaustive }
+    } else {
+        quote! { finish }
+    };
+
+    let (pattern, bound) = bind_fields(&s.fields, "v", |field| {
+        is_debug_printed(field, trait_to_impl)
+    });
+    let adapter = uses_debug_with(s.fields.iter()).then(debug_with_adapter);
+    let body = debug_fields(&s.fields, name, &bound, finish);
+
+    Ok(quote! {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            #adapter
+            let Self #pattern = self;
+            #body
+        }
+    })
 }
 
-fn debug_enum(e: &ItemEnum) -> TokenStream {
+/// Prints the name of a fieldless enum's variant. Where the variant's discriminant is its index, the
+/// name is looked up in a table of them, and otherwise is given by a `match` on the variant.
+fn debug_fieldless_enum(e: &ItemEnum, traits: &DerivedList) -> TokenStream {
+    let names = e
+        .variants
+        .iter()
+        .map(|v| match VariantOptions::of(v).rename {
+            Some(rename) => rename.value(),
+            None => v.ident.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let indexed = e.variants.iter().all(|v| v.discriminant.is_none());
+
+    let name = if is_castable(e, traits) && indexed {
+        let count = names.len();
+        quote! {
+            {
+                const NAMES: [&str; #count] = [#(#names),*];
+                NAMES[*self as usize]
+            }
+        }
+    } else {
+        let idents = e.variants.iter().map(|v| &v.ident);
+        quote! {
+            match self {
+                #(
+                    Self::#idents { .. } => #names,
+                )*
+            }
+        }
+    };
+    quote! {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(#name)
+        }
+    }
+}
+
+fn debug_enum(e: &ItemEnum, trait_to_impl: &DerivedType) -> TokenStream {
     let variant_cases = e
         .variants
         .iter()
         .map(|v| {
             let ident = v.ident.clone();
-            let name = quote! { stringify!(#ident) };
-            match &v.fields {
-                Fields::Named(names) => {
-                    let idents = get_named_idents(names);
-
-                    quote! {
-                        Self::#ident{#(#idents),*}
-                            => f.debug_struct(#name)
-                                #(
-                                    .field(stringify!(#idents), #idents)
-                                )*
-                                    .finish()
-                    }
-                }
-                Fields::Unnamed(unnamed) => {
-                    let idents = get_unnamed_idents(unnamed);
+            let (pattern, bound) = bind_fields(&v.fields, "v", |field| {
+                is_debug_printed(field, trait_to_impl)
+            });
+            let name = match VariantOptions::of(v).rename {
+                Some(rename) => quote! { #rename },
+                None => quote! { stringify!(#ident) },
+            };
+            let body = debug_fields(&v.fields, name, &bound, quote! { finish });
 
-                    quote! {
-                        Self::#ident(#(#idents),*)
-                            => f.debug_tuple(#name)
-                                #(
-                                    .field(#idents)
-                                )*
-                                    .finish()
-                    }
-                }
-                Fields::Unit => quote! {
-                    Self::#ident => f.debug_tuple(#name).finish()
-                },
+            quote! {
+                Self::#ident #pattern => #body
             }
         })
         .collect::<Vec<_>>();
 
+    let adapter =
+        uses_debug_with(e.variants.iter().flat_map(|v| v.fields.iter())).then(debug_with_adapter);
     quote! {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            #adapter
             match self {
                 #(
                     #variant_cases,
@@ -832,36 +2456,43 @@ fn debug_enum(e: &ItemEnum) -> TokenStream {
     }
 }
 
+fn default_field(field: &Field) -> TokenStream {
+    match FieldOptions::of(field).default {
+        Some(FieldDefault::Expr(_, expr)) => quote! { #expr },
+        Some(FieldDefault::With(_, with)) => quote! { #with() },
+        None => quote! { Default::default() },
+    }
+}
+
 fn default_inner(fields: &Fields, root: TokenStream) -> TokenStream {
+    let defaults = fields.iter().map(default_field);
     match &fields {
         Fields::Named(names) => {
             let idents = get_named_idents(names);
 
             quote! {
+                #[inline]
                 fn default() -> Self {
                     #root {
                         #(
-                            #idents : Default::default(),
+                            #idents : #defaults,
                         )*
                     }
                 }
             }
         }
-        Fields::Unnamed(unnamed) => {
-            let idents = get_unnamed_idents(unnamed);
-            let defaults = idents.into_iter().map(|_| quote! {Default::default()});
-
-            quote! {
-                fn default() -> Self {
-                    #root (
-                        #(
-                            #defaults
-                        ),*
-                    )
-                }
+        Fields::Unnamed(_) => quote! {
+            #[inline]
+            fn default() -> Self {
+                #root (
+                    #(
+                        #defaults
+                    ),*
+                )
             }
-        }
+        },
         Fields::Unit => quote! {
+            #[inline]
             fn default() -> Self {
                 #root
             }
@@ -873,9 +2504,12 @@ fn default_struct(s: &ItemStruct) -> TokenStream {
     default_inner(&s.fields, quote! { Self })
 }
 
-fn default_enum(e: &ItemEnum) -> TokenStream {
-    let default_variant = get_debug_enum_marker(e);
+fn default_enum(e: &ItemEnum) -> syn::Result<TokenStream> {
+    let default_variant = get_debug_enum_marker(e)?;
     let default_ident = default_variant.ident.clone();
 
-    default_inner(&default_variant.fields, quote! { Self::#default_ident })
+    Ok(default_inner(
+        &default_variant.fields,
+        quote! { Self::#default_ident },
+    ))
 }
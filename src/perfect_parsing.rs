@@ -5,8 +5,11 @@ use quote::ToTokens;
 use syn::parse::discouraged::Speculative;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{
-    parse_quote, Generics, ItemEnum, ItemStruct, Path, Token, TraitBound, TraitBoundModifier,
+    parenthesized, parse_quote, token, Attribute, Expr, ExprLit, ExprPath, Field, Generics,
+    ItemEnum, ItemStruct, ItemUnion, Lit, LitInt, LitStr, Member, Meta, MetaNameValue, Path, Token,
+    TraitBound, TraitBoundModifier, Type, Variant,
 };
 
 #[cps::cps]
@@ -24,9 +27,24 @@ macro_rules! supported_types_enum {
 
 supported_types_enum!(pub enum DerivedTypeEnum);
 
+impl DerivedTypeEnum {
+    /// The options that may be given in parentheses after the trait name, e.g. `PartialOrd(operators)`.
+    pub fn supported_options(&self) -> &'static [&'static str] {
+        match self {
+            DerivedTypeEnum::PartialEq => &["cheap_first", "structural_match"],
+            DerivedTypeEnum::PartialOrd => &["operators"],
+            DerivedTypeEnum::Hash => &["stable"],
+            DerivedTypeEnum::Debug => &["transparent", "non_exhaustive", "with_generics"],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct DerivedType {
     pub name: DerivedTypeEnum,
     pub span: Span,
+    pub options: Vec<Ident>,
 }
 
 #[cps::cps]
@@ -53,6 +71,14 @@ impl DerivedType {
         type_enum_ident_as_path!(self)
     }
 
+    pub fn option(&self, option: &str) -> Option<&Ident> {
+        self.options.iter().find(|o| *o == option)
+    }
+
+    pub fn has_option(&self, option: &str) -> bool {
+        self.option(option).is_some()
+    }
+
     pub fn get_trait(&self) -> TraitBound {
         let path = self.path();
         TraitBound {
@@ -75,7 +101,8 @@ macro_rules! parse_types_enum {
         match $e {
             $(stringify!($type_name) => Ok(Self {
                 name: DerivedTypeEnum::$type_name,
-                span: $ident.span()
+                span: $ident.span(),
+                options: Vec::new(),
             }),)*
             _ => $err
         }
@@ -88,26 +115,647 @@ impl Parse for DerivedType {
         let binding = ident.to_string();
         let name = binding.as_ref();
 
-        parse_types_enum! {
+        let mut derived: Self = parse_types_enum! {
             match name {
                 ident...,
                 _ => Err(input.error(format!("type identifier {} is not supported - did you mean to use #[derive(...)]?", ident)))
             }
+        }?;
+
+        if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let options = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            for option in options {
+                if !derived
+                    .name
+                    .supported_options()
+                    .contains(&option.to_string().as_str())
+                {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        format!(
+                            "{} is not an option supported by {:?}",
+                            option, derived.name
+                        ),
+                    ));
+                }
+                derived.options.push(option);
+            }
         }
+
+        Ok(derived)
     }
 }
 
-pub struct DerivedList(pub Punctuated<DerivedType, Token![,]>);
+/// The traits to derive, alongside any options applying to the whole item, given as `name = value`.
+pub struct DerivedList(pub Vec<DerivedType>, pub ContainerOptions);
+
+impl DerivedList {
+    pub fn contains(&self, name: DerivedTypeEnum) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn get(&self, name: DerivedTypeEnum) -> Option<&DerivedType> {
+        self.0.iter().find(|derived| derived.name == name)
+    }
+}
 
 impl Parse for DerivedList {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        Ok(Self(Punctuated::parse_terminated(input)?))
+        let mut traits = Vec::new();
+        let mut options = ContainerOptions::default();
+        while !input.is_empty() {
+            if ContainerOptions::peek_option(input) {
+                options.parse_option(input)?;
+            } else {
+                traits.push(input.parse()?);
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(Self(traits, options))
+    }
+}
+
+/// Options applying to every field of the item, given alongside the traits to derive.
+#[derive(Default)]
+pub struct ContainerOptions {
+    /// Set if float fields are compared and hashed by their total order.
+    pub float: Option<LitStr>,
+    /// The fields of a struct which alone are compared and hashed, in the order they are compared.
+    pub key: Option<(Ident, Vec<Member>)>,
+    /// Set if an enum is compared and hashed by its variant alone.
+    pub by_variant: Option<Ident>,
+}
+
+impl ContainerOptions {
+    /// Whether the input starts with an option, rather than a trait.
+    fn peek_option(input: ParseStream) -> bool {
+        let is_flag = input
+            .fork()
+            .parse::<Ident>()
+            .is_ok_and(|ident| ident == "by_variant");
+        is_flag || (input.peek(syn::Ident) && input.peek2(Token![=]))
+    }
+
+    fn parse_option(&mut self, input: ParseStream) -> syn::Result<()> {
+        let option: Ident = input.parse()?;
+        let duplicate = match option.to_string().as_str() {
+            "by_variant" => self.by_variant.replace(option.clone()).is_some(),
+            "float" => {
+                input.parse::<Token![=]>()?;
+                self.float.replace(parse_float_mode(input)?).is_some()
+            }
+            "key" => {
+                input.parse::<Token![=]>()?;
+                let members = if input.peek(token::Paren) {
+                    let content;
+                    parenthesized!(content in input);
+                    Punctuated::<Member, Token![,]>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect()
+                } else {
+                    vec![input.parse()?]
+                };
+                if members.is_empty() {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        "the key must name at least one field",
+                    ));
+                }
+                self.key.replace((option.clone(), members)).is_some()
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    option.span(),
+                    format!("{} is not a supported option", option),
+                ));
+            }
+        };
+        if duplicate {
+            return Err(syn::Error::new(
+                option.span(),
+                format!("{} can only be given once", option),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parses the way floats are compared, of which only `"total"` is supported.
+fn parse_float_mode(input: ParseStream) -> syn::Result<LitStr> {
+    let mode: LitStr = input.parse()?;
+    if mode.value() != "total" {
+        return Err(syn::Error::new(
+            mode.span(),
+            "the only supported float mode is \"total\"",
+        ));
+    }
+    Ok(mode)
+}
+
+/// Whether an attribute on a field or variant gives it options, as `#[perfect_derive(...)]`.
+pub fn is_options_attribute(a: &Attribute) -> bool {
+    a.path().is_ident("perfect_derive")
+}
+
+/// Options given to a single field through `#[perfect_derive(...)]`.
+#[derive(Default)]
+pub struct FieldOptions {
+    /// The traits whose implementations ignore this field.
+    pub skip: Vec<DerivedType>,
+    /// How the field is printed by `Debug`.
+    pub debug: DebugFieldOptions,
+    /// The value given to the field by `Default`, in place of `Default::default()`.
+    pub default: Option<FieldDefault>,
+    /// How the field is ordered by `Ord` and `PartialOrd`.
+    pub order: Option<FieldOrder>,
+    /// Functions called on the field in place of the methods of the given traits.
+    pub with: Vec<(DerivedType, Path)>,
+    /// Set if the field is a pointer compared and hashed by the address it points to.
+    pub by_ptr: Option<Ident>,
+    /// Set if the field holds floats compared and hashed by their total order.
+    pub float: Option<LitStr>,
+    /// Where the field is compared by `PartialEq(cheap_first)`, with higher priorities compared first.
+    pub priority: Option<(Ident, i32)>,
+}
+
+/// Options given to a single field through `#[perfect_derive(order(...))]`.
+pub struct FieldOrder {
+    /// The `order` naming these options.
+    pub option: Ident,
+    /// Set if the field is ordered from greatest to least.
+    pub reverse: Option<Ident>,
+    /// A function giving the key the field is ordered by.
+    pub by: Option<Path>,
+    /// The type required to be ordered in place of the type of the field, for keys borrowing from it.
+    pub key: Option<Type>,
+}
+
+impl FieldOrder {
+    fn parse_options(option: Ident, input: ParseStream) -> syn::Result<Self> {
+        let mut order = Self {
+            option,
+            reverse: None,
+            by: None,
+            key: None,
+        };
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            let duplicate = match option.to_string().as_str() {
+                "reverse" => order.reverse.replace(option.clone()).is_some(),
+                "by" => {
+                    input.parse::<Token![=]>()?;
+                    order.by.replace(input.parse()?).is_some()
+                }
+                "key" => {
+                    input.parse::<Token![=]>()?;
+                    order.key.replace(input.parse()?).is_some()
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        "expected one of `reverse`, `by = path::to::fn` or `key = Type`",
+                    ))
+                }
+            };
+            if duplicate {
+                return Err(syn::Error::new(
+                    option.span(),
+                    format!("{} can only be given once", option),
+                ));
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        match (&order.by, &order.key) {
+            (None, Some(key)) => {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "a key type can only be given alongside a key function, as in `order(by = path::to::fn, key = Type)`",
+                ))
+            }
+            (Some(by), None) => {
+                return Err(syn::Error::new_spanned(
+                    by,
+                    "a key function must be given the type it requires to be ordered, as in `order(by = path::to::fn, key = Type)`",
+                ))
+            }
+            _ => {}
+        }
+        Ok(order)
+    }
+}
+
+/// A value for a field given through `#[perfect_derive(default = ...)]` or
+/// `#[perfect_derive(default_with = ...)]`.
+pub enum FieldDefault {
+    /// An expression evaluating to the value.
+    Expr(Ident, Expr),
+    /// A function taking no arguments and returning the value.
+    With(Ident, Path),
+}
+
+impl FieldDefault {
+    pub fn span(&self) -> Span {
+        match self {
+            FieldDefault::Expr(option, _) | FieldDefault::With(option, _) => option.span(),
+        }
+    }
+}
+
+/// Options given to a single field through `#[perfect_derive(Debug(...))]`.
+#[derive(Default)]
+pub struct DebugFieldOptions {
+    /// The span of the `Debug` naming these options.
+    pub span: Option<Span>,
+    /// The name to print the field under, in place of its identifier.
+    pub rename: Option<LitStr>,
+    /// Set if the field's value is hidden.
+    pub redact: Option<Ident>,
+    /// A function formatting the field in place of its `Debug` implementation.
+    pub with: Option<Path>,
+}
+
+impl DebugFieldOptions {
+    fn parse_options(&mut self, input: ParseStream) -> syn::Result<()> {
+        let options = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+        for option in options {
+            match &option {
+                Meta::Path(path) if path.is_ident("redact") => {
+                    self.redact = path.get_ident().cloned();
+                }
+                Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("rename") => {
+                    match value {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(rename),
+                            ..
+                        }) => self.rename = Some(rename.clone()),
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected a string literal to rename the field to",
+                            ))
+                        }
+                    }
+                }
+                Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("with") => {
+                    match value {
+                        Expr::Path(ExprPath { path, .. }) => {
+                            if self.with.replace(path.clone()).is_some() {
+                                return Err(syn::Error::new(
+                                    option.span(),
+                                    "a field can only be given one function for Debug",
+                                ));
+                            }
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected the path of a function formatting the field",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        "expected one of `rename = \"...\"`, `redact` or `with = path::to::fn`",
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FieldOptions {
+    /// The traits which may be given to `skip(...)`.
+    const SKIPPABLE: &'static [DerivedTypeEnum] = &[
+        DerivedTypeEnum::PartialEq,
+        DerivedTypeEnum::PartialOrd,
+        DerivedTypeEnum::Ord,
+        DerivedTypeEnum::Hash,
+        DerivedTypeEnum::Debug,
+    ];
+
+    /// The traits which compare or hash fields, and so use the address of a field given `by_ptr` or the
+    /// total order of a field given `float = "total"`.
+    pub const COMPARING: &'static [DerivedTypeEnum] = &[
+        DerivedTypeEnum::PartialEq,
+        DerivedTypeEnum::Eq,
+        DerivedTypeEnum::PartialOrd,
+        DerivedTypeEnum::Ord,
+        DerivedTypeEnum::Hash,
+    ];
+
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| is_options_attribute(a)) {
+            attr.parse_args_with(|input: ParseStream| options.parse_options(input))?;
+        }
+        Ok(options)
+    }
+
+    /// Gets the options of a field, which must already have been validated by `from_attrs`.
+    pub fn of(field: &Field) -> Self {
+        Self::from_attrs(&field.attrs).expect("field options are validated before code generation")
+    }
+
+    fn parse_options(&mut self, input: ParseStream) -> syn::Result<()> {
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            match option.to_string().as_str() {
+                "skip" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let skipped = Punctuated::<DerivedType, Token![,]>::parse_terminated(&content)?;
+                    for derived in skipped {
+                        if !Self::SKIPPABLE.contains(&derived.name) {
+                            return Err(syn::Error::new(
+                                derived.span,
+                                format!("fields cannot be skipped by {:?}", derived.name),
+                            ));
+                        }
+                        self.skip.push(derived);
+                    }
+                }
+                "Debug" => {
+                    let content;
+                    parenthesized!(content in input);
+                    self.debug.span = Some(option.span());
+                    self.debug.parse_options(&content)?;
+                }
+                "with" => {
+                    let content;
+                    parenthesized!(content in input);
+                    while !content.is_empty() {
+                        let derived: DerivedType = content.parse()?;
+                        content.parse::<Token![=]>()?;
+                        let with: Path = content.parse()?;
+                        self.add_with(derived, with)?;
+
+                        if content.is_empty() {
+                            break;
+                        }
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+                "PartialEq" => {
+                    let content;
+                    parenthesized!(content in input);
+                    self.parse_eq_options(&content)?;
+                }
+                "by_ptr" => self.by_ptr = Some(option),
+                "float" => {
+                    input.parse::<Token![=]>()?;
+                    self.float = Some(parse_float_mode(input)?);
+                }
+                "order" => {
+                    let content;
+                    parenthesized!(content in input);
+                    if self.order.is_some() {
+                        return Err(syn::Error::new(
+                            option.span(),
+                            "a field can only be given one order(...)",
+                        ));
+                    }
+                    self.order = Some(FieldOrder::parse_options(option, &content)?);
+                }
+                "default" | "default_with" => {
+                    if self.default.is_some() {
+                        return Err(syn::Error::new(
+                            option.span(),
+                            "a field can only be given one default",
+                        ));
+                    }
+                    input.parse::<Token![=]>()?;
+                    self.default = Some(if option == "default" {
+                        FieldDefault::Expr(option, input.parse()?)
+                    } else {
+                        FieldDefault::With(option, input.parse()?)
+                    });
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        format!("{} is not a supported field option", option),
+                    ))
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(())
+    }
+
+    fn parse_eq_options(&mut self, input: ParseStream) -> syn::Result<()> {
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            if option != "priority" {
+                return Err(syn::Error::new(
+                    option.span(),
+                    format!("{} is not a supported PartialEq field option", option),
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            let negative = input.parse::<Option<Token![-]>>()?.is_some();
+            let priority = input.parse::<LitInt>()?.base10_parse::<i32>()?;
+            let priority = if negative { -priority } else { priority };
+            if self.priority.replace((option.clone(), priority)).is_some() {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "a field can only be given one priority",
+                ));
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(())
+    }
+
+    fn add_with(&mut self, derived: DerivedType, with: Path) -> syn::Result<()> {
+        let duplicate = match derived.name {
+            DerivedTypeEnum::Copy | DerivedTypeEnum::Eq => {
+                return Err(syn::Error::new(
+                    derived.span,
+                    format!(
+                        "{:?} has no methods, so no function can be given for it",
+                        derived.name
+                    ),
+                ))
+            }
+            // These already have options of their own, so share them
+            DerivedTypeEnum::Debug => {
+                self.debug.span.get_or_insert(derived.span);
+                self.debug.with.replace(with).is_some()
+            }
+            DerivedTypeEnum::Default => {
+                let option = Ident::new("with", derived.span);
+                self.default
+                    .replace(FieldDefault::With(option, with))
+                    .is_some()
+            }
+            _ => {
+                let duplicate = self.with_fn(derived.name).is_some();
+                self.with.push((derived.clone(), with));
+                duplicate
+            }
+        };
+        if duplicate {
+            return Err(syn::Error::new(
+                derived.span,
+                format!(
+                    "a field can only be given one function for {:?}",
+                    derived.name
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The function called in place of the method of the given trait, if there is one.
+    pub fn with_fn(&self, name: DerivedTypeEnum) -> Option<&Path> {
+        self.with
+            .iter()
+            .find(|(derived, _)| derived.name == name)
+            .map(|(_, with)| with)
+    }
+
+    /// The span naming the trait a function is given for, if there is one.
+    pub fn with_span(&self, name: DerivedTypeEnum) -> Option<Span> {
+        self.with
+            .iter()
+            .find(|(derived, _)| derived.name == name)
+            .map(|(derived, _)| derived.span)
+    }
+
+    /// Whether the field's implementation of the given trait is replaced, by skipping the field,
+    /// calling a function in place of its method or comparing its address, so that the field needn't
+    /// implement it. `Eq` has no methods of its own, so it follows `PartialEq`.
+    pub fn replaces(&self, name: DerivedTypeEnum) -> bool {
+        let name = match name {
+            DerivedTypeEnum::Eq => DerivedTypeEnum::PartialEq,
+            name => name,
+        };
+        self.skips(name)
+            || self.with_fn(name).is_some()
+            || self.compares_by_ptr(name)
+            || self.compares_total(name)
+    }
+
+    /// Whether the field's floats are compared or hashed by their total order for the given trait.
+    pub fn compares_total(&self, name: DerivedTypeEnum) -> bool {
+        self.float.is_some() && Self::COMPARING.contains(&name)
+    }
+
+    /// Whether the field is compared or hashed by its address for the given trait.
+    pub fn compares_by_ptr(&self, name: DerivedTypeEnum) -> bool {
+        self.by_ptr.is_some() && Self::COMPARING.contains(&name)
+    }
+
+    /// The skip naming the given trait, if there is one.
+    pub fn skipped(&self, name: DerivedTypeEnum) -> Option<&DerivedType> {
+        self.skip.iter().find(|derived| derived.name == name)
+    }
+
+    /// Whether the field is left out of the implementation of the given trait. `Eq` has no body of
+    /// its own, so it follows `PartialEq`.
+    pub fn skips(&self, name: DerivedTypeEnum) -> bool {
+        let name = match name {
+            DerivedTypeEnum::Eq => DerivedTypeEnum::PartialEq,
+            name => name,
+        };
+        self.skipped(name).is_some()
+    }
+
+    pub fn has_debug_options(&self) -> bool {
+        self.debug.span.is_some() || self.skips(DerivedTypeEnum::Debug)
+    }
+}
+
+/// Options given to a single enum variant through `#[perfect_derive(...)]`.
+#[derive(Default)]
+pub struct VariantOptions {
+    /// The position of the variant when ordering the variants, in place of its discriminant.
+    pub order: Option<(Ident, Expr)>,
+    /// The name to print the variant under in `Debug`.
+    pub rename: Option<LitStr>,
+    /// The tag hashed for the variant by `Hash(stable)`, in place of its index.
+    pub hash_tag: Option<(Ident, Expr)>,
+}
+
+impl VariantOptions {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| is_options_attribute(a)) {
+            attr.parse_args_with(|input: ParseStream| options.parse_options(input))?;
+        }
+        Ok(options)
+    }
+
+    /// Gets the options of a variant, which must already have been validated by `from_attrs`.
+    pub fn of(variant: &Variant) -> Self {
+        Self::from_attrs(&variant.attrs)
+            .expect("variant options are validated before code generation")
+    }
+
+    fn parse_options(&mut self, input: ParseStream) -> syn::Result<()> {
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let duplicate = match option.to_string().as_str() {
+                "order" => self
+                    .order
+                    .replace((option.clone(), input.parse()?))
+                    .is_some(),
+                "rename" => self.rename.replace(input.parse()?).is_some(),
+                "hash_tag" => self
+                    .hash_tag
+                    .replace((option.clone(), input.parse()?))
+                    .is_some(),
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        format!("{} is not a supported variant option", option),
+                    ))
+                }
+            };
+            if duplicate {
+                return Err(syn::Error::new(
+                    option.span(),
+                    format!("a variant can only be given one {}", option),
+                ));
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(())
     }
 }
 
+#[derive(Clone)]
 pub enum StructOrEnum {
     Struct(ItemStruct),
     Enum(ItemEnum),
+    Union(ItemUnion),
 }
 
 impl StructOrEnum {
@@ -115,6 +763,7 @@ impl StructOrEnum {
         match self {
             StructOrEnum::Struct(s) => s.ident.clone(),
             StructOrEnum::Enum(e) => e.ident.clone(),
+            StructOrEnum::Union(u) => u.ident.clone(),
         }
     }
 
@@ -122,6 +771,29 @@ impl StructOrEnum {
         match self {
             StructOrEnum::Struct(s) => s.generics.clone(),
             StructOrEnum::Enum(e) => e.generics.clone(),
+            StructOrEnum::Union(u) => u.generics.clone(),
+        }
+    }
+
+    /// Every field of the item, across all variants of an enum.
+    pub fn fields(&self) -> Vec<&Field> {
+        match self {
+            StructOrEnum::Struct(s) => s.fields.iter().collect(),
+            StructOrEnum::Enum(e) => e.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+            StructOrEnum::Union(u) => u.fields.named.iter().collect(),
+        }
+    }
+
+    /// Every field of the item, mutably.
+    pub fn fields_mut(&mut self) -> Vec<&mut Field> {
+        match self {
+            StructOrEnum::Struct(s) => s.fields.iter_mut().collect(),
+            StructOrEnum::Enum(e) => e
+                .variants
+                .iter_mut()
+                .flat_map(|v| v.fields.iter_mut())
+                .collect(),
+            StructOrEnum::Union(u) => u.fields.named.iter_mut().collect(),
         }
     }
 }
@@ -132,10 +804,17 @@ impl Parse for StructOrEnum {
         let enum_parse = enum_fork.parse::<ItemEnum>();
         if let Ok(enum_val) = enum_parse {
             input.advance_to(&enum_fork);
-            Ok(Self::Enum(enum_val))
-        } else {
-            Ok(Self::Struct(input.parse()?))
+            return Ok(Self::Enum(enum_val));
+        }
+
+        let union_fork = input.fork();
+        let union_parse = union_fork.parse::<ItemUnion>();
+        if let Ok(union_val) = union_parse {
+            input.advance_to(&union_fork);
+            return Ok(Self::Union(union_val));
         }
+
+        Ok(Self::Struct(input.parse()?))
     }
 }
 
@@ -144,6 +823,7 @@ impl ToTokens for StructOrEnum {
         match self {
             StructOrEnum::Struct(s) => s.to_tokens(tokens),
             StructOrEnum::Enum(e) => e.to_tokens(tokens),
+            StructOrEnum::Union(u) => u.to_tokens(tokens),
         }
     }
 }
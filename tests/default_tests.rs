@@ -5,6 +5,7 @@ pub struct Error {}
 
 #[perfect_derive(Clone)]
 pub struct ResultWrapper<Ok, Err = Error> {
+    #[allow(unused)]
     data: Rc<Result<Ok, Err>>,
 }
 
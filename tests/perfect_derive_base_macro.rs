@@ -1,4 +1,7 @@
-use std::hash::Hash;
+// The float tests clone arrays of floats, which are `Copy`
+#![allow(clippy::clone_on_copy)]
+
+use std::hash::{Hash, Hasher};
 
 use perfect_derive::perfect_derive;
 
@@ -154,6 +157,7 @@ enum EverythingEnumCore {
 }
 
 #[perfect_derive(Copy, Clone, Ord, Eq, PartialOrd, PartialEq, Debug, Hash, Default)]
+#[allow(unused)]
 enum EverythingEnum {
     #[default]
     E1,
@@ -262,6 +266,18 @@ pub fn copy_struct_eq() {
     assert!(s1.eq(&s2));
 }
 
+#[test]
+pub fn clone_copy_enum_eq() {
+    let s1 = EverythingEnum::E5 {
+        name1: 4401,
+        name2: (),
+    };
+
+    let s2 = s1.clone();
+
+    assert!(s1.eq(&s2));
+}
+
 #[test]
 pub fn copy_unit_enum_eq() {
     let s1 = EverythingEnum::E1;
@@ -597,3 +613,1246 @@ fn float_unnamed_enum_debug_matches() {
         }
     }
 }
+
+#[derive(Copy, Clone, Ord, Eq, PartialOrd, PartialEq, Debug)]
+enum DiscriminantEnumCore {
+    High = 2,
+    Low = 1,
+    Lowest = -4,
+    AfterLowest,
+    Highest = 1 << 4,
+}
+
+#[perfect_derive(Copy, Clone, Ord, Eq, PartialOrd, PartialEq, Debug)]
+enum DiscriminantEnum {
+    High = 2,
+    Low = 1,
+    Lowest = -4,
+    AfterLowest,
+    Highest = 1 << 4,
+}
+
+#[test]
+fn discriminant_enum_ord_matches() {
+    let cs = [
+        DiscriminantEnumCore::High,
+        DiscriminantEnumCore::Low,
+        DiscriminantEnumCore::Lowest,
+        DiscriminantEnumCore::AfterLowest,
+        DiscriminantEnumCore::Highest,
+    ];
+    let ss = [
+        DiscriminantEnum::High,
+        DiscriminantEnum::Low,
+        DiscriminantEnum::Lowest,
+        DiscriminantEnum::AfterLowest,
+        DiscriminantEnum::Highest,
+    ];
+    for (c1, s1) in cs.iter().zip(ss.iter()) {
+        for (c2, s2) in cs.iter().zip(ss.iter()) {
+            assert_eq!(
+                s1.cmp(s2),
+                c1.cmp(c2),
+                "{c1:?} <= {c2:?} is not {s1:?} <= {s2:?}"
+            );
+            assert_eq!(
+                s1.partial_cmp(s2),
+                c1.partial_cmp(c2),
+                "{c1:?} <= {c2:?} is not {s1:?} <= {s2:?}"
+            );
+        }
+    }
+}
+
+#[derive(Copy, Clone, Ord, Eq, PartialOrd, PartialEq, Debug)]
+#[repr(u8)]
+enum ReprEnumCore {
+    V1(u32) = 200,
+    V2 { v: i8 } = 7,
+    V3,
+}
+
+#[perfect_derive(Copy, Clone, Ord, Eq, PartialOrd, PartialEq, Debug)]
+#[repr(u8)]
+enum ReprEnum {
+    V1(u32) = 200,
+    V2 { v: i8 } = 7,
+    V3,
+}
+
+#[test]
+fn repr_enum_ord_matches() {
+    let cs = [
+        ReprEnumCore::V1(3),
+        ReprEnumCore::V2 { v: -1 },
+        ReprEnumCore::V3,
+    ];
+    let ss = [ReprEnum::V1(3), ReprEnum::V2 { v: -1 }, ReprEnum::V3];
+    for (c1, s1) in cs.iter().zip(ss.iter()) {
+        for (c2, s2) in cs.iter().zip(ss.iter()) {
+            assert_eq!(
+                s1.cmp(s2),
+                c1.cmp(c2),
+                "{c1:?} <= {c2:?} is not {s1:?} <= {s2:?}"
+            );
+            assert_eq!(
+                s1.partial_cmp(s2),
+                c1.partial_cmp(c2),
+                "{c1:?} <= {c2:?} is not {s1:?} <= {s2:?}"
+            );
+        }
+    }
+}
+
+// comparison must stop at the first field that isn't equal
+#[derive(PartialEq, Eq)]
+struct PanicsOnCmp;
+
+impl PartialOrd for PanicsOnCmp {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PanicsOnCmp {
+    fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+        panic!("compared a field after the ordering was already decided")
+    }
+}
+
+#[perfect_derive(PartialEq, Eq, Ord, PartialOrd)]
+struct ShortCircuitStruct {
+    key: u32,
+    rest: PanicsOnCmp,
+}
+
+#[perfect_derive(PartialEq, Eq, Ord, PartialOrd)]
+enum ShortCircuitEnum {
+    V1(u32, PanicsOnCmp),
+}
+
+#[test]
+fn ord_short_circuits() {
+    let s1 = ShortCircuitStruct {
+        key: 1,
+        rest: PanicsOnCmp,
+    };
+    let s2 = ShortCircuitStruct {
+        key: 2,
+        rest: PanicsOnCmp,
+    };
+    assert_eq!(s1.cmp(&s2), std::cmp::Ordering::Less);
+    assert_eq!(s1.partial_cmp(&s2), Some(std::cmp::Ordering::Less));
+
+    let e1 = ShortCircuitEnum::V1(2, PanicsOnCmp);
+    let e2 = ShortCircuitEnum::V1(1, PanicsOnCmp);
+    assert_eq!(e1.cmp(&e2), std::cmp::Ordering::Greater);
+    assert_eq!(e1.partial_cmp(&e2), Some(std::cmp::Ordering::Greater));
+}
+
+// records whether a value was last written by `clone_from` rather than `clone`
+#[derive(Debug)]
+struct CloneFromTracker(bool);
+
+impl Clone for CloneFromTracker {
+    fn clone(&self) -> Self {
+        Self(false)
+    }
+
+    fn clone_from(&mut self, _: &Self) {
+        self.0 = true;
+    }
+}
+
+#[perfect_derive(Clone)]
+struct CloneFromStruct {
+    v1: CloneFromTracker,
+    v2: Vec<u8>,
+}
+
+#[perfect_derive(Clone)]
+struct CloneFromUnnamedStruct(CloneFromTracker, Vec<u8>);
+
+#[perfect_derive(Clone)]
+#[allow(unused)]
+enum CloneFromEnum {
+    V1 { v1: CloneFromTracker },
+    V2(CloneFromTracker, Vec<u8>),
+}
+
+#[test]
+fn struct_clone_from_reuses_fields() {
+    let source = CloneFromStruct {
+        v1: CloneFromTracker(false),
+        v2: vec![1, 2, 3],
+    };
+    let mut target = source.clone();
+    assert!(!target.v1.0);
+
+    target.clone_from(&source);
+    assert!(target.v1.0);
+    assert_eq!(target.v2, source.v2);
+
+    let source = CloneFromUnnamedStruct(CloneFromTracker(false), vec![4, 5]);
+    let mut target = source.clone();
+    target.clone_from(&source);
+    assert!(target.0 .0);
+    assert_eq!(target.1, source.1);
+}
+
+#[test]
+fn enum_clone_from_reuses_matching_variant() {
+    let source = CloneFromEnum::V2(CloneFromTracker(false), vec![1, 2, 3]);
+    let mut target = CloneFromEnum::V1 {
+        v1: CloneFromTracker(true),
+    };
+
+    // different variants fall back to `clone`
+    target.clone_from(&source);
+    let CloneFromEnum::V2(tracker, data) = &target else {
+        panic!("clone_from did not change the variant");
+    };
+    assert!(!tracker.0);
+    assert_eq!(data, &vec![1, 2, 3]);
+
+    // the same variant is cloned field by field
+    target.clone_from(&source);
+    let CloneFromEnum::V2(tracker, _) = &target else {
+        panic!("clone_from changed the variant");
+    };
+    assert!(tracker.0);
+}
+
+#[perfect_derive(Copy, Clone, PartialOrd(operators), PartialEq, Debug)]
+struct PordOperatorsStruct(f32, f32);
+
+#[perfect_derive(Copy, Clone, PartialOrd(operators), PartialEq, Debug)]
+enum PordOperatorsEnum {
+    V1 { v1: f32, v2: f32 },
+    V2,
+}
+
+#[test]
+fn float_partial_ord_operators_match() {
+    let vs = [f32::NAN, f32::NEG_INFINITY, -1.0, 0.0, 1.0, f32::INFINITY];
+    for v1 in vs {
+        for v2 in vs {
+            for v3 in vs {
+                for v4 in vs {
+                    let c1 = PordNamedEnumCore::V1 { v1, v2 };
+                    let c2 = PordNamedEnumCore::V1 { v1: v3, v2: v4 };
+
+                    let s1 = PordOperatorsStruct(v1, v2);
+                    let s2 = PordOperatorsStruct(v3, v4);
+                    assert_eq!(s1 < s2, c1 < c2, "{c1:?} < {c2:?} is not {s1:?} < {s2:?}");
+                    assert_eq!(
+                        s1 <= s2,
+                        c1 <= c2,
+                        "{c1:?} <= {c2:?} is not {s1:?} <= {s2:?}"
+                    );
+                    assert_eq!(s1 > s2, c1 > c2, "{c1:?} > {c2:?} is not {s1:?} > {s2:?}");
+                    assert_eq!(
+                        s1 >= s2,
+                        c1 >= c2,
+                        "{c1:?} >= {c2:?} is not {s1:?} >= {s2:?}"
+                    );
+
+                    let e1 = PordOperatorsEnum::V1 { v1, v2 };
+                    let e2 = PordOperatorsEnum::V1 { v1: v3, v2: v4 };
+                    assert_eq!(e1 < e2, c1 < c2, "{c1:?} < {c2:?} is not {e1:?} < {e2:?}");
+                    assert_eq!(
+                        e1 <= e2,
+                        c1 <= c2,
+                        "{c1:?} <= {c2:?} is not {e1:?} <= {e2:?}"
+                    );
+                    assert_eq!(e1 > e2, c1 > c2, "{c1:?} > {c2:?} is not {e1:?} > {e2:?}");
+                    assert_eq!(
+                        e1 >= e2,
+                        c1 >= c2,
+                        "{c1:?} >= {c2:?} is not {e1:?} >= {e2:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    let c1 = PordNamedEnumCore::V1 { v1: 0.0, v2: 0.0 };
+    let c2 = PordNamedEnumCore::V2;
+    let e1 = PordOperatorsEnum::V1 { v1: 0.0, v2: 0.0 };
+    let e2 = PordOperatorsEnum::V2;
+    assert_eq!(e1 < e2, c1 < c2);
+    assert_eq!(e1 <= e2, c1 <= c2);
+    assert_eq!(e1 > e2, c1 > c2);
+    assert_eq!(e1 >= e2, c1 >= c2);
+    assert_eq!(e2 <= e2, c2 <= c2);
+}
+
+#[perfect_derive(Copy, Clone)]
+union IntOrFloat {
+    i: u32,
+    f: f32,
+}
+
+#[perfect_derive(Copy, Clone)]
+union GenericUnion<T: Copy> {
+    v: T,
+    raw: u64,
+}
+
+#[test]
+fn union_clone_copies()
+where
+    GenericUnion<u8>: Clone,
+{
+    let u1 = IntOrFloat { f: 1.5 };
+    let u2 = u1.clone();
+    assert_eq!(unsafe { u2.i }, 1.5f32.to_bits());
+
+    let g1 = GenericUnion::<u16> { raw: u64::MAX };
+    let g2 = g1.clone();
+    assert_eq!(unsafe { g2.raw }, u64::MAX);
+    assert_eq!(unsafe { g2.v }, u16::MAX);
+}
+
+#[perfect_derive(Copy, Clone, Ord, Eq, PartialOrd(operators), PartialEq, Debug, Hash)]
+pub enum Never {}
+
+#[perfect_derive(Clone, Eq, PartialOrd(operators), PartialEq, Debug, Hash)]
+pub enum NeverMarker {}
+
+#[test]
+pub fn never_enum_derives()
+where
+    Never: Copy + Clone + Ord + Eq + PartialOrd + PartialEq + std::fmt::Debug + Hash,
+    NeverMarker: Clone + Eq + PartialOrd + PartialEq + std::fmt::Debug + Hash,
+{
+    let none: Option<NeverMarker> = None;
+    assert_eq!(none, None);
+    assert!(none.clone() <= None);
+    assert_eq!(hash_to_int(&none), hash_to_int(&Option::<Never>::None));
+}
+
+#[perfect_derive(Debug(transparent))]
+struct DebugTransparent(#[allow(unused)] Vec<u8>);
+
+#[perfect_derive(Debug(transparent))]
+struct DebugTransparentNamed {
+    #[allow(unused)]
+    inner: Option<&'static str>,
+}
+
+pub struct NotDebug;
+
+#[perfect_derive(Debug(non_exhaustive))]
+pub struct DebugNonExhaustive<T> {
+    pub id: u32,
+    #[allow(unused)]
+    secret: T,
+    pub name: &'static str,
+}
+
+#[perfect_derive(Debug(non_exhaustive))]
+pub struct DebugNonExhaustiveTuple<T>(pub u32, #[allow(unused)] T);
+
+#[perfect_derive(Debug(with_generics))]
+struct DebugWithGenerics<T>(T);
+
+#[test]
+fn debug_container_options() {
+    assert_eq!(format!("{:?}", DebugTransparent(vec![1, 2])), "[1, 2]");
+    assert_eq!(
+        format!("{:?}", DebugTransparentNamed { inner: Some("a") }),
+        "Some(\"a\")"
+    );
+
+    let non_exhaustive = DebugNonExhaustive {
+        id: 3,
+        secret: NotDebug,
+        name: "n",
+    };
+    assert_eq!(
+        format!("{non_exhaustive:?}"),
+        "DebugNonExhaustive { id: 3, name: \"n\", .. }"
+    );
+    assert_eq!(
+        format!("{:?}", DebugNonExhaustiveTuple(5, NotDebug)),
+        "DebugNonExhaustiveTuple(5, ..)"
+    );
+
+    assert_eq!(
+        format!("{:?}", DebugWithGenerics(1u8)),
+        format!("{}(1)", std::any::type_name::<DebugWithGenerics<u8>>())
+    );
+    assert!(format!("{:?}", DebugWithGenerics(1u8)).ends_with("DebugWithGenerics<u8>(1)"));
+}
+
+/// Only comparable through its key, so can't be compared itself.
+struct LastAccess(#[allow(unused)] u64);
+
+#[perfect_derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CacheKey {
+    id: u32,
+    name: &'static str,
+    #[allow(unused)]
+    #[perfect_derive(skip(PartialEq, Hash, PartialOrd, Ord))]
+    last_access: u64,
+}
+
+#[perfect_derive(PartialEq, Eq, Hash)]
+enum CacheEntry {
+    Entry(u32, #[perfect_derive(skip(PartialEq, Hash))] LastAccess),
+    #[allow(unused)]
+    Missing {
+        #[perfect_derive(skip(Hash))]
+        since: u64,
+    },
+}
+
+#[test]
+fn skipped_fields_are_ignored() {
+    let a = CacheKey {
+        id: 1,
+        name: "a",
+        last_access: 10,
+    };
+    let b = CacheKey {
+        last_access: 20,
+        ..a.clone()
+    };
+    assert_eq!(a, b);
+    assert_eq!(hash_to_int(&a), hash_to_int(&b));
+    assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+    assert_eq!(a.partial_cmp(&b), Some(std::cmp::Ordering::Equal));
+    assert!(format!("{a:?}").contains("last_access: 10"));
+
+    let c = CacheKey { id: 2, ..a.clone() };
+    assert_ne!(a, c);
+    assert!(a < c);
+
+    let x = CacheEntry::Entry(1, LastAccess(10));
+    let y = CacheEntry::Entry(1, LastAccess(20));
+    assert!(x == y);
+    assert_eq!(hash_to_int(&x), hash_to_int(&y));
+    assert!(x != CacheEntry::Entry(2, LastAccess(10)));
+    assert!(CacheEntry::Missing { since: 1 } != CacheEntry::Missing { since: 2 });
+}
+
+#[allow(unused)]
+fn debug_hex(value: &u32, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{value:#x}")
+}
+
+#[allow(unused)]
+#[perfect_derive(Debug)]
+struct Credentials<T> {
+    #[perfect_derive(Debug(rename = "user"))]
+    username: &'static str,
+    #[perfect_derive(Debug(redact))]
+    token: T,
+    #[perfect_derive(Debug(with = debug_hex))]
+    flags: u32,
+    #[perfect_derive(skip(Debug))]
+    cache: NotDebug,
+}
+
+#[allow(unused)]
+#[perfect_derive(Debug)]
+enum Login<T> {
+    Token(
+        #[perfect_derive(Debug(redact))] T,
+        #[perfect_derive(skip(Debug))] NotDebug,
+    ),
+    Password {
+        #[perfect_derive(Debug(rename = "user"))]
+        username: &'static str,
+        #[perfect_derive(Debug(with = debug_hex))]
+        hint: u32,
+    },
+}
+
+#[test]
+fn debug_field_options() {
+    let credentials = Credentials {
+        username: "admin",
+        token: NotDebug,
+        flags: 255,
+        cache: NotDebug,
+    };
+    assert_eq!(
+        format!("{credentials:?}"),
+        "Credentials { user: \"admin\", token: \"<redacted>\", flags: 0xff }"
+    );
+
+    assert_eq!(
+        format!("{:?}", Login::Token(NotDebug, NotDebug)),
+        "Token(\"<redacted>\")"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            Login::<NotDebug>::Password {
+                username: "admin",
+                hint: 16
+            }
+        ),
+        "Password { user: \"admin\", hint: 0x10 }"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct NoDefault;
+
+fn default_name() -> String {
+    "unnamed".to_string()
+}
+
+#[perfect_derive(Default, Debug, PartialEq)]
+struct RetryConfig<T> {
+    #[perfect_derive(default = 3)]
+    retries: u32,
+    #[perfect_derive(default_with = default_name)]
+    name: String,
+    #[perfect_derive(default = Vec::new())]
+    pending: Vec<T>,
+    timeout: Option<u64>,
+}
+
+#[perfect_derive(Default)]
+struct DefaultTuple(#[perfect_derive(default = NoDefault)] NoDefault, u8);
+
+#[perfect_derive(Default, Debug, PartialEq)]
+enum Connection<T> {
+    #[default]
+    Idle {
+        #[perfect_derive(default = 30)]
+        keep_alive: u32,
+        #[perfect_derive(default = None)]
+        last: Option<T>,
+    },
+    #[allow(unused)]
+    Open(T),
+}
+
+#[test]
+fn field_defaults() {
+    assert_eq!(
+        RetryConfig::<NoDefault>::default(),
+        RetryConfig {
+            retries: 3,
+            name: "unnamed".to_string(),
+            pending: Vec::new(),
+            timeout: None,
+        }
+    );
+
+    let DefaultTuple(NoDefault, n) = DefaultTuple::default();
+    assert_eq!(n, 0);
+
+    assert!(
+        Connection::<NoDefault>::default()
+            == Connection::Idle {
+                keep_alive: 30,
+                last: None,
+            }
+    );
+}
+
+/// Orders shorter strings first, and strings of the same length alphabetically. Only equal strings
+/// have equal keys, so ordering by it agrees with `PartialEq`.
+fn shortlex(s: &&'static str) -> (usize, &'static str) {
+    (s.len(), s)
+}
+
+fn shortlex_slice<T>(v: &[T]) -> (usize, &[T]) {
+    (v.len(), v)
+}
+
+#[perfect_derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct ScheduledTask {
+    #[perfect_derive(order(reverse))]
+    priority: u8,
+    id: u32,
+}
+
+#[perfect_derive(PartialEq, PartialOrd)]
+struct ByKey<T> {
+    #[perfect_derive(order(by = shortlex, key = (usize, &'static str), reverse))]
+    name: &'static str,
+    #[perfect_derive(order(by = shortlex_slice, key = T))]
+    values: Vec<T>,
+}
+
+#[perfect_derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Job {
+    Queued(#[perfect_derive(order(reverse))] u8),
+    Running {
+        #[perfect_derive(order(by = shortlex, key = (usize, &'static str)))]
+        name: &'static str,
+    },
+}
+
+#[test]
+fn field_order_options() {
+    let mut tasks = vec![
+        ScheduledTask { priority: 1, id: 2 },
+        ScheduledTask { priority: 3, id: 5 },
+        ScheduledTask { priority: 1, id: 1 },
+        ScheduledTask { priority: 3, id: 4 },
+    ];
+    tasks.sort();
+    assert_eq!(
+        tasks,
+        vec![
+            ScheduledTask { priority: 3, id: 4 },
+            ScheduledTask { priority: 3, id: 5 },
+            ScheduledTask { priority: 1, id: 1 },
+            ScheduledTask { priority: 1, id: 2 },
+        ]
+    );
+    assert!(ScheduledTask { priority: 2, id: 9 } < ScheduledTask { priority: 1, id: 0 });
+
+    let a = ByKey {
+        name: "long",
+        values: vec![f64::NAN],
+    };
+    let b = ByKey {
+        name: "sh",
+        values: vec![1.0],
+    };
+    assert_eq!(a.partial_cmp(&b), Some(std::cmp::Ordering::Less));
+    let c = ByKey {
+        name: "four",
+        values: vec![2.0, 0.0],
+    };
+    let d = ByKey {
+        name: "four",
+        values: vec![1.0, 5.0],
+    };
+    assert_eq!(c.partial_cmp(&d), Some(std::cmp::Ordering::Greater));
+    assert!(c > d);
+    let e = ByKey {
+        name: "four",
+        values: vec![9.0],
+    };
+    assert!(e < d);
+
+    assert!(Job::Queued(5) < Job::Queued(1));
+    assert!(Job::Running { name: "b" } < Job::Running { name: "aa" });
+    assert!(Job::Running { name: "a" } < Job::Running { name: "b" });
+    assert_eq!(
+        Job::Running { name: "a" }.cmp(&Job::Running { name: "a" }),
+        std::cmp::Ordering::Equal
+    );
+    assert!(Job::Queued(0) < Job::Running { name: "" });
+}
+
+mod ignore_case {
+    use std::hash::{Hash, Hasher};
+
+    pub fn eq(a: &&'static str, b: &&'static str) -> bool {
+        a.eq_ignore_ascii_case(b)
+    }
+
+    pub fn hash<H: Hasher>(s: &&'static str, state: &mut H) {
+        s.to_ascii_lowercase().hash(state)
+    }
+
+    pub fn partial_cmp(a: &&'static str, b: &&'static str) -> Option<std::cmp::Ordering> {
+        Some(cmp(a, b))
+    }
+
+    pub fn cmp(a: &&'static str, b: &&'static str) -> std::cmp::Ordering {
+        a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase())
+    }
+}
+
+/// Can only be cloned by a function.
+struct Handle(u32);
+
+#[allow(unused)]
+fn clone_handle(handle: &Handle) -> Handle {
+    Handle(handle.0 + 1)
+}
+
+#[perfect_derive(Clone, PartialEq, Hash, PartialOrd(operators))]
+struct Username {
+    #[perfect_derive(with(
+        PartialEq = ignore_case::eq,
+        Hash = ignore_case::hash,
+        PartialOrd = ignore_case::partial_cmp
+    ))]
+    name: &'static str,
+    #[perfect_derive(with(
+        Clone = clone_handle,
+        PartialEq = handle_eq,
+        PartialOrd = handle_partial_cmp
+    ), skip(Hash))]
+    handle: Handle,
+}
+
+fn handle_eq(a: &Handle, b: &Handle) -> bool {
+    a.0 == b.0
+}
+
+fn handle_partial_cmp(a: &Handle, b: &Handle) -> Option<std::cmp::Ordering> {
+    a.0.partial_cmp(&b.0)
+}
+
+#[perfect_derive(Clone, PartialEq, Eq, Ord, PartialOrd)]
+enum Tag {
+    Named(
+        #[perfect_derive(with(PartialEq = ignore_case::eq, Ord = ignore_case::cmp))] &'static str,
+    ),
+    #[allow(unused)]
+    Handle(
+        #[perfect_derive(with(Clone = clone_handle, PartialEq = handle_eq, Ord = handle_cmp))]
+        Handle,
+    ),
+}
+
+fn handle_cmp(a: &Handle, b: &Handle) -> std::cmp::Ordering {
+    a.0.cmp(&b.0)
+}
+
+#[allow(unused)]
+fn next_generation(generation: &u32) -> u32 {
+    generation + 1
+}
+
+/// `Copy`, but with a field cloned by a function, which `Clone` must still call.
+#[perfect_derive(Clone, Copy)]
+struct Versioned {
+    #[perfect_derive(with(Clone = next_generation))]
+    generation: u32,
+    value: u8,
+}
+
+#[test]
+fn field_with_functions() {
+    let versioned = Versioned {
+        generation: 1,
+        value: 7,
+    };
+    let cloned = Clone::clone(&versioned);
+    assert_eq!((cloned.generation, cloned.value), (2, 7));
+    let copied = versioned;
+    assert_eq!((copied.generation, copied.value), (1, 7));
+
+    let a = Username {
+        name: "Admin",
+        handle: Handle(1),
+    };
+    let b = Username {
+        name: "admin",
+        handle: Handle(1),
+    };
+    assert!(a == b);
+    assert_eq!(hash_to_int(&a), hash_to_int(&b));
+    assert_eq!(a.partial_cmp(&b), Some(std::cmp::Ordering::Equal));
+    assert!(a <= b);
+    assert!(a >= b);
+    assert!(!a.lt(&b));
+    assert!(!a.gt(&b));
+
+    let c = a.clone();
+    assert_eq!(c.handle.0, 2);
+    assert!(c > a);
+    assert!(a.lt(&c));
+
+    let mut d = b.clone();
+    d.clone_from(&c);
+    assert_eq!(d.handle.0, 3);
+    assert_eq!(d.name, "Admin");
+
+    assert!(Tag::Named("A") == Tag::Named("a"));
+    assert_eq!(
+        Tag::Named("b").cmp(&Tag::Named("A")),
+        std::cmp::Ordering::Greater
+    );
+    assert!(Tag::Named("b") > Tag::Named("A"));
+    assert!(matches!(
+        Tag::Handle(Handle(1)).clone(),
+        Tag::Handle(Handle(2))
+    ));
+}
+
+#[perfect_derive(Clone, PartialEq, Eq, Hash, PartialOrd(operators), Ord)]
+struct GraphNode<T> {
+    id: u32,
+    #[perfect_derive(by_ptr)]
+    value: std::rc::Rc<T>,
+    #[perfect_derive(by_ptr)]
+    shared: std::sync::Arc<dyn std::fmt::Debug + Send + Sync>,
+}
+
+#[perfect_derive(PartialEq, Eq, Hash)]
+enum Edge<T> {
+    To(#[perfect_derive(by_ptr)] std::rc::Rc<T>),
+    #[allow(unused)]
+    Dangling,
+}
+
+#[test]
+fn by_ptr_fields_compare_addresses() {
+    let shared: std::sync::Arc<dyn std::fmt::Debug + Send + Sync> = std::sync::Arc::new(1);
+    let value = std::rc::Rc::new(NotDebug);
+    let a = GraphNode {
+        id: 1,
+        value: value.clone(),
+        shared: shared.clone(),
+    };
+    let b = a.clone();
+    assert!(a == b);
+    assert_eq!(hash_to_int(&a), hash_to_int(&b));
+    assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+    assert!(a <= b);
+
+    let c = GraphNode {
+        value: std::rc::Rc::new(NotDebug),
+        ..a.clone()
+    };
+    assert!(a != c);
+    assert_eq!(
+        a.cmp(&c),
+        std::rc::Rc::as_ptr(&a.value).cmp(&std::rc::Rc::as_ptr(&c.value))
+    );
+    assert_eq!(
+        a < c,
+        std::rc::Rc::as_ptr(&a.value) < std::rc::Rc::as_ptr(&c.value)
+    );
+
+    assert!(Edge::To(value.clone()) == Edge::To(value.clone()));
+    assert!(Edge::To(value.clone()) != Edge::To(std::rc::Rc::new(NotDebug)));
+    assert_eq!(
+        hash_to_int(&Edge::To(value.clone())),
+        hash_to_int(&Edge::To(value))
+    );
+}
+
+#[perfect_derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd(operators),
+    Ord,
+    float = "total"
+)]
+struct TotalPoint<T> {
+    x: f64,
+    y: f32,
+    weights: [Option<f32>; 2],
+    label: T,
+}
+
+#[perfect_derive(Debug, PartialEq, Eq, Hash)]
+enum TotalShape {
+    Circle(#[perfect_derive(float = "total")] f64),
+    #[allow(unused)]
+    Missing(#[perfect_derive(float = "total")] Option<f64>),
+    Scaled(#[perfect_derive(float = "total", skip(Hash))] f32),
+}
+
+#[perfect_derive(PartialEq, Eq, Hash, float = "total")]
+struct TotalSample {
+    value: f64,
+    #[perfect_derive(skip(Hash))]
+    weight: f32,
+}
+
+#[test]
+fn float_total_order() {
+    let a = TotalPoint {
+        x: f64::NAN,
+        y: 1.0,
+        weights: [Some(1.0), None],
+        label: "a",
+    };
+    assert!(a == a.clone());
+    assert_eq!(hash_to_int(&a), hash_to_int(&a.clone()));
+
+    let b = TotalPoint {
+        x: 1.0,
+        ..a.clone()
+    };
+    assert_eq!(a.cmp(&b), f64::NAN.total_cmp(&1.0));
+    assert!(b < a);
+
+    let c = TotalPoint {
+        weights: [Some(1.0), Some(-0.0)],
+        ..a.clone()
+    };
+    assert!(a < c);
+    let d = TotalPoint {
+        weights: [Some(1.0), Some(0.0)],
+        ..a.clone()
+    };
+    assert!(c < d);
+    assert!(c != d);
+    assert_ne!(hash_to_int(&c), hash_to_int(&d));
+
+    assert!(TotalShape::Circle(f64::NAN) == TotalShape::Circle(f64::NAN));
+    assert!(TotalShape::Circle(0.0) != TotalShape::Circle(-0.0));
+    assert_eq!(
+        hash_to_int(&TotalShape::Circle(f64::NAN)),
+        hash_to_int(&TotalShape::Circle(f64::NAN))
+    );
+    assert!(TotalShape::Scaled(f32::NAN) == TotalShape::Scaled(f32::NAN));
+    assert_eq!(
+        hash_to_int(&TotalShape::Scaled(0.0)),
+        hash_to_int(&TotalShape::Scaled(1.0))
+    );
+
+    let e = TotalSample {
+        value: 1.0,
+        weight: f32::NAN,
+    };
+    let f = TotalSample {
+        value: 1.0,
+        weight: 2.0,
+    };
+    assert!(e == e);
+    assert!(e != f);
+    assert_eq!(hash_to_int(&e), hash_to_int(&f));
+}
+
+#[perfect_derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd(operators), Ord)]
+enum Severity {
+    #[perfect_derive(order = 2, rename = "warn")]
+    Warning,
+    #[perfect_derive(order = 0)]
+    Info,
+    // Numbered after the previous variant, as discriminants are
+    Notice,
+    #[perfect_derive(order = 10, rename = "ERROR")]
+    Error {
+        code: u32,
+    },
+}
+
+#[test]
+fn variant_order_and_rename() {
+    assert!(Severity::Info < Severity::Notice);
+    assert!(Severity::Notice < Severity::Warning);
+    assert!(Severity::Warning < Severity::Error { code: 0 });
+    assert!(Severity::Error { code: 0 } < Severity::Error { code: 1 });
+    assert_eq!(
+        Severity::Warning.partial_cmp(&Severity::Info),
+        Some(std::cmp::Ordering::Greater)
+    );
+
+    let mut all = vec![
+        Severity::Error { code: 3 },
+        Severity::Warning,
+        Severity::Notice,
+        Severity::Info,
+    ];
+    all.sort();
+    assert_eq!(
+        format!("{all:?}"),
+        "[Info, Notice, warn, ERROR { code: 3 }]"
+    );
+}
+
+#[perfect_derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, key = id)]
+#[allow(unused)]
+struct Entity<T> {
+    name: String,
+    id: u64,
+    payload: T,
+}
+
+#[perfect_derive(PartialEq, Eq, PartialOrd(operators), Ord, Hash, key = (tenant, id))]
+#[allow(unused)]
+struct TenantEntity {
+    id: u64,
+    cache: Vec<u8>,
+    tenant: u32,
+}
+
+#[perfect_derive(Debug, PartialEq, Eq, Hash, by_variant)]
+enum Message<T> {
+    Ping(u32),
+    Data { body: T },
+}
+
+#[test]
+fn key_and_by_variant() {
+    struct NotComparable;
+
+    let a = Entity {
+        name: "a".to_string(),
+        id: 1,
+        payload: NotComparable,
+    };
+    let b = Entity {
+        name: "b".to_string(),
+        id: 1,
+        payload: NotComparable,
+    };
+    let c = Entity {
+        name: "a".to_string(),
+        id: 2,
+        payload: NotComparable,
+    };
+    assert!(a == b);
+    assert_eq!(hash_to_int(&a), hash_to_int(&b));
+    assert!(a < c);
+    assert!(a != c);
+
+    // Compared by tenant first, as it is named first in the key
+    let first = TenantEntity {
+        id: 2,
+        cache: vec![1],
+        tenant: 1,
+    };
+    let second = TenantEntity {
+        id: 1,
+        cache: vec![],
+        tenant: 2,
+    };
+    assert!(first < second);
+    assert!(first.lt(&second));
+    assert_eq!(first.cmp(&second), std::cmp::Ordering::Less);
+    assert!(
+        first
+            == TenantEntity {
+                cache: vec![2, 3],
+                ..first
+            }
+    );
+
+    assert!(Message::<NotComparable>::Ping(1) == Message::Ping(2));
+    assert!(
+        Message::Ping(1)
+            != Message::Data {
+                body: NotComparable
+            }
+    );
+    assert_eq!(
+        hash_to_int(&Message::<NotComparable>::Ping(1)),
+        hash_to_int(&Message::<NotComparable>::Ping(2))
+    );
+}
+
+/// Panics when compared, showing which fields `PartialEq` reaches.
+#[allow(unused)]
+struct Tripwire;
+
+impl PartialEq for Tripwire {
+    fn eq(&self, _: &Self) -> bool {
+        panic!("compared a field expected to be compared last")
+    }
+}
+
+/// The payload is compared last, and is a `Tripwire` where the other fields are expected to differ.
+#[perfect_derive(PartialEq(cheap_first))]
+#[allow(unused)]
+struct Packet<P> {
+    payload: P,
+    version: u32,
+    checksum: Option<(u8, char)>,
+    #[perfect_derive(PartialEq(priority = 2))]
+    kind: String,
+}
+
+#[perfect_derive(PartialEq(cheap_first))]
+#[allow(unused)]
+enum Frame<P> {
+    Data(P, usize),
+    Close {
+        #[perfect_derive(PartialEq(priority = -1))]
+        reason: P,
+        code: Vec<u16>,
+    },
+}
+
+#[test]
+fn cheap_first_equality() {
+    let packet = |version, checksum, kind: &str| Packet {
+        payload: Tripwire,
+        version,
+        checksum,
+        kind: kind.to_string(),
+    };
+    assert!(packet(1, None, "a") != packet(2, None, "a"));
+    assert!(packet(1, Some((1, 'x')), "a") != packet(1, Some((2, 'x')), "a"));
+    assert!(packet(1, None, "a") != packet(1, None, "b"));
+
+    // With every other field equal, the payload is compared, and decides the result
+    let bytes = |payload: &[u8]| Packet {
+        payload: payload.to_vec(),
+        version: 1,
+        checksum: Some((1, 'x')),
+        kind: "a".to_string(),
+    };
+    assert!(bytes(&[1, 2]) == bytes(&[1, 2]));
+    assert!(bytes(&[1, 2]) != bytes(&[1, 3]));
+
+    assert!(Frame::Data(Tripwire, 1) != Frame::Data(Tripwire, 2));
+    assert!(
+        Frame::Close {
+            reason: Tripwire,
+            code: vec![1],
+        } != Frame::Close {
+            reason: Tripwire,
+            code: vec![2],
+        }
+    );
+    assert!(Frame::Data("x", 1) == Frame::Data("x", 1));
+    assert!(
+        Frame::Close {
+            reason: "x",
+            code: vec![1],
+        } == Frame::Close {
+            reason: "x",
+            code: vec![1],
+        }
+    );
+    assert!(
+        Frame::Close {
+            reason: "x",
+            code: vec![1],
+        } != Frame::Close {
+            reason: "y",
+            code: vec![1],
+        }
+    );
+}
+
+#[perfect_derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd(operators), Ord, Hash)]
+enum Channel {
+    Red,
+    Green,
+    #[perfect_derive(rename = "B")]
+    Blue,
+}
+
+#[perfect_derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+enum Opcode {
+    Halt = 0xff,
+    Nop = 0,
+    Push,
+    Pop = 0x10,
+}
+
+#[perfect_derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Phase {
+    #[perfect_derive(order = 1)]
+    Started(),
+    #[perfect_derive(order = 0, rename = "waiting")]
+    Pending {},
+    #[perfect_derive(order = 5)]
+    Done,
+}
+
+#[test]
+fn fieldless_enums() {
+    assert_eq!(
+        format!("{:?}", [Channel::Red, Channel::Green, Channel::Blue]),
+        "[Red, Green, B]"
+    );
+    assert!(Channel::Red < Channel::Green);
+    assert!(Channel::Blue.ge(&Channel::Green));
+    assert!(Channel::Green == Clone::clone(&Channel::Green));
+    assert_ne!(hash_to_int(&Channel::Red), hash_to_int(&Channel::Green));
+
+    assert_eq!(format!("{:?}", Opcode::Halt), "Halt");
+    assert_eq!(format!("{:?}", Opcode::Pop), "Pop");
+    let mut ops = vec![Opcode::Halt, Opcode::Pop, Opcode::Push, Opcode::Nop];
+    ops.sort();
+    assert!(ops == [Opcode::Nop, Opcode::Push, Opcode::Pop, Opcode::Halt]);
+    assert_eq!(hash_to_int(&Opcode::Push), hash_to_int(&Opcode::Push));
+
+    assert_eq!(
+        format!("{:?}", [Phase::Started(), Phase::Pending {}, Phase::Done]),
+        "[Started, waiting, Done]"
+    );
+    assert!(Phase::Pending {} < Phase::Started());
+    assert!(Phase::Started() < Phase::Done);
+    assert!(Phase::Done.clone() == Phase::Done);
+    assert!(Phase::Done != Phase::Started());
+    assert_eq!(
+        Phase::Started().partial_cmp(&Phase::Started()),
+        Some(std::cmp::Ordering::Equal)
+    );
+}
+
+/// Records the bytes written to it, to check exactly what is hashed.
+#[derive(Default)]
+struct RecordingHasher(Vec<u8>);
+
+impl Hasher for RecordingHasher {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+fn hashed_bytes<T: Hash>(value: &T) -> Vec<u8> {
+    let mut hasher = RecordingHasher::default();
+    value.hash(&mut hasher);
+    hasher.0
+}
+
+#[perfect_derive(PartialEq, Eq, Hash(stable))]
+enum StoredKey {
+    Empty,
+    Byte(u8),
+    Pair {
+        first: u8,
+        #[perfect_derive(skip(PartialEq, Hash))]
+        ignored: u64,
+        second: u8,
+    },
+}
+
+#[perfect_derive(PartialEq, Eq, Hash(stable))]
+enum TaggedKey {
+    #[perfect_derive(hash_tag = 0x100)]
+    Old,
+    // Tagged after the previous variant, as discriminants are
+    New,
+    #[perfect_derive(hash_tag = 7)]
+    Byte(u8),
+}
+
+#[test]
+fn stable_enum_hashing() {
+    assert_eq!(hashed_bytes(&StoredKey::Empty), [0, 0, 0, 0]);
+    assert_eq!(hashed_bytes(&StoredKey::Byte(9)), [1, 0, 0, 0, 9]);
+    assert_eq!(
+        hashed_bytes(&StoredKey::Pair {
+            first: 2,
+            ignored: u64::MAX,
+            second: 3,
+        }),
+        [2, 0, 0, 0, 2, 3]
+    );
+
+    assert_eq!(hashed_bytes(&TaggedKey::Old), [0, 1, 0, 0]);
+    assert_eq!(hashed_bytes(&TaggedKey::New), [1, 1, 0, 0]);
+    assert_eq!(hashed_bytes(&TaggedKey::Byte(5)), [7, 0, 0, 0, 5]);
+}
+
+#[perfect_derive(Clone, Debug, PartialEq)]
+struct CfgFields<T, #[cfg(any())] U, #[cfg(not(any()))] V> {
+    t: T,
+    #[cfg(any())]
+    u: U,
+    #[cfg(not(any()))]
+    v: V,
+}
+
+#[test]
+fn cfg_generic_fields_follow_their_parameters() {
+    let value = CfgFields { t: 1, v: "v" };
+    assert_eq!(value.clone(), value);
+    assert_ne!(value, CfgFields { t: 1, v: "w" });
+    assert_eq!(format!("{:?}", value), r#"CfgFields { t: 1, v: "v" }"#);
+}
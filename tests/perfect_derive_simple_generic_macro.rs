@@ -41,6 +41,54 @@ macro_rules! make_test {
                 phantom: std::marker::PhantomData<&'a T>,
             }
 
+            #[perfect_derive($trait_name $(,$trait_name_tail)*)]
+            pub struct Struct6<
+                #[cfg(all())]
+                T,
+                #[cfg(any())]
+                U,
+                #[cfg(not(any()))]
+                const VALUE: usize,
+            > {
+                v1: [T; VALUE],
+            }
+
+            #[perfect_derive($trait_name $(,$trait_name_tail)*)]
+            pub struct Struct7<
+                T,
+                #[cfg(any())]
+                U,
+            > {
+                v1: T,
+                #[cfg(any())]
+                v2: U,
+                #[cfg(any())]
+                v3: bool,
+            }
+
+            #[perfect_derive($trait_name $(,$trait_name_tail)*)]
+            pub struct Struct8<
+                T,
+                #[cfg(all())]
+                U,
+            > (
+                T,
+                #[cfg(all())]
+                U,
+            );
+
+            #[perfect_derive($trait_name $(,$trait_name_tail)*)]
+            pub enum Enum2<
+                T,
+                #[cfg(any())]
+                U,
+            > {
+                E1(T, #[cfg(any())] U),
+                #[cfg(any())]
+                E2 { name1: U },
+                E3,
+            }
+
             #[perfect_derive($trait_name $(,$trait_name_tail)*)]
             pub enum Enum<U, T> {
                 E1,
@@ -64,6 +112,10 @@ macro_rules! make_test {
                     Struct3<u64, u64>: $trait_name,
                     Struct4<'static, 'static, isize>: $trait_name,
                     Struct5<'static, isize, 12>: $trait_name,
+                    Struct6<isize, 3>: $trait_name,
+                    Struct7<isize>: $trait_name,
+                    Struct8<isize, u8>: $trait_name,
+                    Enum2<isize>: $trait_name,
                     Enum<i64, ()>: $trait_name,
                 {
                     // No need to do anything
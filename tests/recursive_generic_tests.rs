@@ -7,6 +7,7 @@ pub struct NonClonable {}
 #[perfect_derive(Clone)]
 pub struct List<T> {
     data: Rc<T>,
+    #[allow(unused)]
     next: Option<Rc<List<T>>>,
 }
 
@@ -0,0 +1,39 @@
+#![cfg(nightly)]
+#![feature(structural_match)]
+
+use perfect_derive::perfect_derive;
+use std::marker::PhantomData;
+
+pub struct NonComparable {}
+
+#[perfect_derive(PartialEq(structural_match), Eq)]
+pub enum State<T> {
+    Idle,
+    Running(u32, PhantomData<T>),
+}
+
+const IDLE: State<NonComparable> = State::Idle;
+const STARTED: State<NonComparable> = State::Running(0, PhantomData);
+
+#[perfect_derive(PartialEq(structural_match), Eq)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+const ORIGIN: Point = Point { x: 0, y: 0 };
+
+#[test]
+fn constants_can_be_used_as_patterns() {
+    let describe = |state: &State<NonComparable>| match *state {
+        IDLE => "idle",
+        STARTED => "started",
+        State::Running(..) => "running",
+    };
+    assert_eq!(describe(&State::Idle), "idle");
+    assert_eq!(describe(&State::Running(0, PhantomData)), "started");
+    assert_eq!(describe(&State::Running(3, PhantomData)), "running");
+
+    assert!(matches!(Point { x: 0, y: 0 }, ORIGIN));
+    assert!(!matches!(Point { x: 0, y: 1 }, ORIGIN));
+}